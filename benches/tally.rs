@@ -0,0 +1,77 @@
+// tally.rs
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use votes::{gen_random_choices, gen_random_contest, gen_random_votes, Tally};
+
+
+fn bench_result(c: &mut Criterion) {
+
+    let contest = gen_random_contest(3, gen_random_choices(10));
+
+    let mut group = c.benchmark_group("result");
+    group.sample_size(10);
+
+    for count in [100_000, 1_000_000, 10_000_000] {
+
+        let tally = Tally::new(&contest)
+            .with_votes(gen_random_votes(count, &contest));
+
+        group.bench_with_input(BenchmarkId::new("sequential", count), &tally, |b, tally| {
+            b.iter(|| tally.result())
+        });
+
+        #[cfg(feature = "rayon")]
+        group.bench_with_input(BenchmarkId::new("parallel", count), &tally, |b, tally| {
+            b.iter(|| tally.result_parallel())
+        });
+
+    }
+
+    group.finish();
+
+}
+
+#[cfg(feature = "bincode")]
+fn bench_parse(c: &mut Criterion) {
+
+    use votes::FlatVote;
+
+    let contest = gen_random_contest(3, gen_random_choices(10));
+    let votes = gen_random_votes(100_000, &contest);
+
+    let json: Vec<String> = votes.iter()
+        .map(|v| serde_json::to_string(v).expect("Failed to encode vote"))
+        .collect();
+    let bin: Vec<Vec<u8>> = votes.iter()
+        .map(|v| v.to_bytes().expect("Failed to encode vote"))
+        .collect();
+
+    // Report encoded sizes alongside the timings
+    let json_size: usize = json.iter().map(|v| v.len() + 1).sum();
+    let bin_size: usize = bin.iter().map(|v| v.len() + 4).sum();
+    println!("Encoded size for {} votes: JSON {} bytes, binary {} bytes", votes.len(), json_size, bin_size);
+
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(10);
+
+    group.bench_function("json", |b| {
+        b.iter(|| json.iter()
+            .map(|v| serde_json::from_str::<FlatVote>(v).expect("Failed to decode vote"))
+            .collect::<Vec<FlatVote>>())
+    });
+
+    group.bench_function("bincode", |b| {
+        b.iter(|| bin.iter()
+            .map(|v| FlatVote::from_bytes(v).expect("Failed to decode vote"))
+            .collect::<Vec<FlatVote>>())
+    });
+
+    group.finish();
+
+}
+
+#[cfg(feature = "bincode")]
+criterion_group!(benches, bench_result, bench_parse);
+#[cfg(not(feature = "bincode"))]
+criterion_group!(benches, bench_result);
+criterion_main!(benches);
@@ -0,0 +1,24 @@
+// write_schemas.rs
+
+//! Writes the JSON Schemas of the file formats to the current directory:
+//! 
+//! ```sh
+//! cargo run --example write_schemas --features schemars
+//! ```
+
+use std::fs;
+
+use votes::schema::{contest_result_schema, contest_schema, flat_vote_schema};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let schemas = [
+        ("contest.schema.json", contest_schema()),
+        ("vote.schema.json", flat_vote_schema()),
+        ("results.schema.json", contest_result_schema()),
+    ];
+    for (fname, schema) in schemas {
+        fs::write(fname, serde_json::to_string_pretty(&schema)?)?;
+        println!("Wrote {}", fname);
+    }
+    Ok(())
+}
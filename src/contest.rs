@@ -1,23 +1,109 @@
 // contest.rs
 
-use std::{fs::File, path::Path};
-use std::io::{self, Read, Write};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::Arc;
 
-use rand::Rng;
+use chrono::{DateTime, Utc};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize, ser::SerializeStruct};
 
+use crate::method::EmbeddedMethod;
+use crate::{csv, fs, DefaultNaming, Error, FileNamingStrategy, PluralityAtLarge, TallyMethod, ValidationError};
+
+/// Sentinel [ContestChoice] ID used to represent write-in votes
+pub const WRITE_IN_ID: i64 = i64::MIN;
+
+/// Version of the serialization format of [Contest]s and
+/// [FlatVote](crate::FlatVote)s written by this library.
+/// 
+/// Files without a version were written before versioning and are migrated
+/// when loaded.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The ID of a [Contest]
+/// 
+/// File names given by a [FileNamingStrategy](crate::FileNamingStrategy)
+/// take the inner `i64`, as election IDs share them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(transparent)]
+pub struct ContestId(pub i64);
+
+impl fmt::Display for ContestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<i64> for ContestId {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ContestId> for i64 {
+    fn from(value: ContestId) -> Self {
+        value.0
+    }
+}
+
 /// A contest with its choices
 /// 
 /// Use [ContestBuilder] to create a new [Contest] with all possible options.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// 
+/// Contests are identified by their ID: equality and hashing only take the ID
+/// into account, so that a contest can be compared or used as a map key
+/// regardless of later changes to e.g. its description. Use
+/// [Contest::content_eq] to compare everything.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Contest {
-    id: i64,
+    id: ContestId,
     description: String,
     tally_type: String,
     num_winners: i64,
     min_choices: i64,
     max_choices: i64,
     choices: Vec<ContestChoice>,
+    #[serde(default)]
+    quorum: Option<u64>,
+    #[serde(default)]
+    allows_write_ins: bool,
+    #[serde(default)]
+    phase: ContestPhase,
+    #[serde(default, with = "chrono::serde::ts_seconds")]
+    #[cfg_attr(feature = "schemars", schemars(with = "i64"))]
+    created_at: DateTime<Utc>,
+    #[serde(default)]
+    eligible_voters: Option<u64>,
+    #[serde(default)]
+    disqualified: Vec<i64>,
+    #[serde(default)]
+    schema_version: u32,
+    #[serde(skip)]
+    #[cfg_attr(feature = "schemars", schemars(skip))]
+    method: Option<EmbeddedMethod>,
+}
+
+/// Lifecycle of a [Contest]. Votes are only accepted while it is open.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum ContestPhase {
+    /// Being prepared, not accepting votes yet
+    Draft,
+    /// Accepting votes
+    #[default]
+    Open,
+    /// No longer accepting votes
+    Closed,
+    /// Closed and kept for the record
+    Archived,
 }
 
 impl Contest {
@@ -27,68 +113,549 @@ impl Contest {
     }
 
     /// The contest ID
-    pub fn id(&self) -> i64 {
+    pub fn id(&self) -> ContestId {
         self.id
     }
 
+    /// Whether every field of both contests matches, not only the ID.
+    /// 
+    /// [Contest::created_at] is left out, as copies of a contest are built
+    /// at a different time.
+    pub fn content_eq(&self, other: &Contest) -> bool {
+        self.id == other.id
+            && self.description == other.description
+            && self.tally_type == other.tally_type
+            && self.num_winners == other.num_winners
+            && self.min_choices == other.min_choices
+            && self.max_choices == other.max_choices
+            && self.choices == other.choices
+            && self.quorum == other.quorum
+            && self.allows_write_ins == other.allows_write_ins
+            && self.phase == other.phase
+            && self.eligible_voters == other.eligible_voters
+            && self.disqualified == other.disqualified
+            && self.schema_version == other.schema_version
+    }
+
     /// All available choices for this contest
     pub fn choices(&self) -> &Vec<ContestChoice> {
         &self.choices
     }
 
+    /// All available choices sorted by display order, with ties broken by ID
+    pub fn choices_ordered(&self) -> Vec<&ContestChoice> {
+        let mut choices: Vec<&ContestChoice> = self.choices.iter().collect();
+        choices.sort_by_key(|c| (c.display_order, c.id));
+        choices
+    }
+
+    /// All available choices in random order, e.g. to give every ballot a
+    /// different order and avoid position bias. See
+    /// [Contest::shuffled_choices_with_seed] for a reproducible order.
+    pub fn shuffled_choices(&self) -> Vec<&ContestChoice> {
+        let mut choices: Vec<&ContestChoice> = self.choices.iter().collect();
+        choices.shuffle(&mut rand::thread_rng());
+        choices
+    }
+
+    /// All available choices in a random order that only depends on `seed`,
+    /// so that the same seed always gives the same order
+    pub fn shuffled_choices_with_seed(&self, seed: u64) -> Vec<&ContestChoice> {
+        let mut choices: Vec<&ContestChoice> = self.choices.iter().collect();
+        choices.shuffle(&mut StdRng::seed_from_u64(seed));
+        choices
+    }
+
+    /// Number of choices
+    pub fn num_choices(&self) -> usize {
+        self.choices.len()
+    }
+
+    /// Same as [Contest::num_choices]
+    pub fn choices_count(&self) -> usize {
+        self.num_choices()
+    }
+
+    /// Whether the contest passes the same checks as
+    /// [ContestBuilder::build_validated], e.g. after being deserialized
+    pub fn is_valid(&self) -> bool {
+        find_duplicate_id(&self.choices).is_none()
+    }
+
+    /// The choice with ID `id`, if any
+    pub fn choice_by_id(&self, id: i64) -> Option<&ContestChoice> {
+        self.choices.iter().find(|c| c.id == id)
+    }
+
+    /// Whether `choice` is one of the choices of this contest, or a write-in
+    /// if this contest allows them
+    pub fn is_choice_valid(&self, choice: &ContestChoice) -> bool {
+        (choice.is_write_in() && self.allows_write_ins) || self.choice_by_id(choice.id).is_some()
+    }
+
+    /// Checks that every choice is valid for this contest, returning the IDs
+    /// of those that are not.
+    /// 
+    /// Unlike [DecodedContestVote::validate], the number of choices is not checked.
+    pub fn are_choices_valid(&self, choices: &[DecodedVoteChoice]) -> Result<(), Vec<i64>> {
+        let invalid: Vec<i64> = choices.iter()
+            .map(|c| &c.contest_choice)
+            .filter(|c| !self.is_choice_valid(c))
+            .map(|c| c.id)
+            .collect();
+        match invalid.is_empty() {
+            true => Ok(()),
+            false => Err(invalid),
+        }
+    }
+
+    /// All choices grouped by their category, in contest order within each group
+    pub fn choices_by_category(&self) -> HashMap<Option<String>, Vec<&ContestChoice>> {
+        let mut groups: HashMap<Option<String>, Vec<&ContestChoice>> = HashMap::new();
+        for choice in &self.choices {
+            groups.entry(choice.category.clone()).or_default().push(choice);
+        }
+        groups
+    }
+
+    /// All choices keyed by their IDs, for repeated lookups
+    pub fn choices_as_map(&self) -> HashMap<i64, &ContestChoice> {
+        self.choices.iter().map(|c| (c.id, c)).collect()
+    }
+
+    /// When the contest was built. It is saved as seconds since the Unix
+    /// epoch, and contests saved before this was recorded report the epoch.
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    /// The current lifecycle phase
+    pub fn phase(&self) -> ContestPhase {
+        self.phase
+    }
+
+    /// Moves the contest to a different lifecycle phase
+    pub fn set_phase(&mut self, phase: ContestPhase) {
+        self.phase = phase;
+    }
+
+    /// Name of the [TallyMethod] used to count votes
+    pub fn tally_type(&self) -> &str {
+        &self.tally_type
+    }
+
+    /// The [TallyMethod] set with [ContestBuilder::tally_method], or
+    /// [PluralityAtLarge] if there is none.
+    /// 
+    /// Only the name of the method is saved with the contest, so loaded
+    /// contests are counted with [PluralityAtLarge] unless the method is given
+    /// to [Tally::with_method](crate::Tally::with_method).
+    pub fn tally_method(&self) -> &dyn TallyMethod {
+        match &self.method {
+            Some(method) => method.0.as_ref(),
+            None => &PluralityAtLarge,
+        }
+    }
+
+    /// Whether the contest was built with its own [TallyMethod]
+    #[cfg(feature = "rayon")]
+    pub(crate) fn has_embedded_method(&self) -> bool {
+        self.method.is_some()
+    }
+
     /// Number of winners
     pub fn num_winners(&self) -> i64 {
         self.num_winners
     }
 
+    /// Minimum number of choices per vote
+    pub fn min_choices(&self) -> i64 {
+        self.min_choices
+    }
+
+    /// Maximum number of choices per vote
+    pub fn max_choices(&self) -> i64 {
+        self.max_choices
+    }
+
+    /// Whether more than one choice wins the contest
+    pub fn is_multi_winner(&self) -> bool {
+        self.num_winners > 1
+    }
+
+    /// Whether a single choice wins the contest
+    pub fn is_single_winner(&self) -> bool {
+        self.num_winners == 1
+    }
+
+    /// Whether voters may select more than one choice
+    pub fn allows_multiple_choices(&self) -> bool {
+        self.max_choices > 1
+    }
+
+    /// Minimum number of valid votes for the result to be binding, if any
+    pub fn quorum(&self) -> Option<u64> {
+        self.quorum
+    }
+
+    /// Whether voters can write in a candidate that is not on the ballot
+    pub fn allows_write_ins(&self) -> bool {
+        self.allows_write_ins
+    }
+
+    /// Number of voters eligible to vote, if known
+    pub fn eligible_voters(&self) -> Option<u64> {
+        self.eligible_voters
+    }
+
+    /// Marks the choice with ID `choice_id` as disqualified, e.g. because the
+    /// candidate turned out to be ineligible. The choice stays in the contest,
+    /// but votes including it are no longer counted by [Tally::result](crate::Tally::result).
+    /// 
+    /// Returns `false` if there is no such choice.
+    pub fn disqualify_choice(&mut self, choice_id: i64) -> bool {
+        if self.choice_by_id(choice_id).is_none() {
+            return false;
+        }
+        if !self.disqualified.contains(&choice_id) {
+            self.disqualified.push(choice_id);
+        }
+        true
+    }
+
+    /// The IDs of the choices disqualified with [Contest::disqualify_choice]
+    pub fn disqualified_choice_ids(&self) -> &[i64] {
+        &self.disqualified
+    }
+
+    /// Takes the choice with ID `choice_id` off the ballot
+    pub(crate) fn remove_choice(&mut self, choice_id: i64) {
+        self.choices.retain(|c| c.id != choice_id);
+        self.disqualified.retain(|id| *id != choice_id);
+    }
+
+    /// Encodes the contest as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(&self)?)
+    }
+
+    /// Decodes a contest from a JSON string
+    pub fn from_json(s: &str) -> Result<Self, Error> {
+        serde_json::from_str::<Self>(s)?.migrate()
+    }
+
+    /// Builds a contest from a CSV list of choices with the columns
+    /// `id,name,party,display_order`, as exported by most election management
+    /// systems. Voters may select up to `num_winners` choices.
+    /// 
+    /// The header row is required, but `party` and `display_order` are
+    /// optional and may be left out or empty. Columns may come in any order.
+    pub fn from_csv_choices<R: Read>(reader: R, num_winners: i64) -> Result<Contest, Error> {
+
+        let mut lines = BufReader::new(reader).lines();
+        let header = lines.next()
+            .ok_or_else(|| Error::CSV("Missing header row".to_owned()))??;
+        let header = csv::split_record(&header)?;
+        let column = |name: &str| header.iter().position(|h| h.trim() == name);
+        let (Some(id_col), Some(name_col)) = (column("id"), column("name")) else {
+            return Err(Error::CSV("Expected `id` and `name` columns".to_owned()));
+        };
+        let party_col = column("party");
+        let order_col = column("display_order");
+
+        let mut choices = vec![];
+        for line in lines {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let fields = csv::split_record(&line)?;
+            let field = |col: Option<usize>| col
+                .and_then(|col| fields.get(col))
+                .map(|f| f.trim())
+                .filter(|f| !f.is_empty());
+            let id = field(Some(id_col))
+                .ok_or_else(|| Error::CSV(format!("Missing choice ID in `{}`", line)))?;
+            let id: i64 = id.parse()
+                .map_err(|_| Error::CSV(format!("Invalid choice ID `{}`", id)))?;
+            let name = fields.get(name_col)
+                .ok_or_else(|| Error::CSV(format!("Missing choice name in `{}`", line)))?;
+            let mut choice = ContestChoice::new(id, name);
+            if let Some(party) = field(party_col) {
+                choice = choice.with_category(party);
+            }
+            if let Some(order) = field(order_col) {
+                let order: u32 = order.parse()
+                    .map_err(|_| Error::CSV(format!("Invalid display order `{}`", order)))?;
+                choice = choice.with_display_order(order);
+            }
+            choices.push(choice);
+        }
+
+        ContestBuilder::new(num_winners, &choices)
+            .max_choices(num_winners)
+            .build_validated()
+
+    }
+
+    /// Writes the choices of this contest as CSV, in the format read by
+    /// [Contest::from_csv_choices]
+    pub fn choices_to_csv<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        writer.write_all("id,name,party,display_order\n".as_bytes())?;
+        for c in &self.choices {
+            writeln!(writer, "{},{},{},{}",
+                c.id,
+                csv::escape_field(&c.text),
+                csv::escape_field(c.category.as_deref().unwrap_or_default()),
+                c.display_order,
+            )?;
+        }
+        Ok(())
+    }
+
     /// Save contest JSON data to a file
-    pub fn save_to_file(&self) -> Result<String, io::Error> {
-        let fname = format!("contest-{}.json", self.id);
-        let mut file = File::create(&fname)?;
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(
+        contest_id = self.id.0,
+        elapsed = tracing::field::Empty,
+    )))]
+    pub fn save_to_file(&self) -> Result<String, Error> {
+        record_elapsed!();
+        self.save_to_file_with(&DefaultNaming)
+    }
+
+    /// Same as [Contest::save_to_file], with the file named by `naming`
+    pub fn save_to_file_with(&self, naming: &dyn FileNamingStrategy) -> Result<String, Error> {
+        let fname = naming.file_name("contest", self.id.0);
         let serialized = serde_json::to_string_pretty(&self)?;
-        file.write_all(serialized.as_bytes())?;
-        file.flush()?;
+        fs::write_atomically(&fname, |file| Ok(file.write_all(serialized.as_bytes())?))?;
         Ok(fname)
     }
 
-    /// Load contest data from a JSON file
-    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, io::Error> {
-        let mut file = File::open(path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        let deserialized: Self = serde_json::from_str(&contents)?;
-        Ok(deserialized)
+    /// The version of the serialization format this contest was written
+    /// with, see [SCHEMA_VERSION]
+    pub fn schema_version(&self) -> u32 {
+        self.schema_version
+    }
+
+    /// Upgrades a contest decoded from an older serialization format to
+    /// [SCHEMA_VERSION]
+    pub(crate) fn migrate(self) -> Result<Self, Error> {
+        match self.schema_version {
+            0 => Ok(Self::migrate_v0_to_v1(self)),
+            SCHEMA_VERSION => Ok(self),
+            version => Err(Error::UnsupportedSchemaVersion(version)),
+        }
+    }
+
+    /// Contests written before versioning have the same fields, with the
+    /// ones added later already filled in with their defaults. Those built
+    /// without a tally type were counted with [PluralityAtLarge].
+    fn migrate_v0_to_v1(self) -> Self {
+        let tally_type = match self.tally_type.is_empty() {
+            true => PluralityAtLarge.name().to_string(),
+            false => self.tally_type,
+        };
+        Self{
+            tally_type,
+            schema_version: 1,
+            ..self
+        }
+    }
+
+    /// Load contest data from a JSON file.
+    /// 
+    /// Files written with an older [SCHEMA_VERSION] are migrated, newer ones
+    /// fail with [Error::UnsupportedSchemaVersion].
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(
+        contest_id = tracing::field::Empty,
+        elapsed = tracing::field::Empty,
+    )))]
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        record_elapsed!();
+        let contest = fs::load_with(path.as_ref(), Self::from_json)?;
+        record!("contest_id", contest.id.0);
+        Ok(contest)
+    }
+
+    /// Saves the contest as a TOML file to `path`
+    #[cfg(feature = "toml")]
+    pub fn save_to_toml<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let serialized = toml::to_string_pretty(self)?;
+        fs::write_atomically(path, |file| Ok(file.write_all(serialized.as_bytes())?))
+    }
+
+    /// Loads a contest from a TOML file written by [Contest::save_to_toml]
+    /// or by hand
+    #[cfg(feature = "toml")]
+    pub fn load_from_toml<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        fs::load_with(path.as_ref(), |contents| toml::from_str::<Self>(contents)?.migrate())
+    }
+
+    /// Encodes the contest as YAML, with the same field names as in JSON
+    #[cfg(feature = "yaml")]
+    pub fn to_yaml(&self) -> Result<String, Error> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+
+    /// Decodes a contest from a YAML string
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(s: &str) -> Result<Self, Error> {
+        serde_yaml::from_str::<Self>(s)?.migrate()
+    }
+
+    /// Saves the contest as a YAML file to `path`
+    #[cfg(feature = "yaml")]
+    pub fn save_to_yaml<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let serialized = self.to_yaml()?;
+        fs::write_atomically(path, |file| Ok(file.write_all(serialized.as_bytes())?))
+    }
+
+    /// Loads a contest from a YAML file
+    #[cfg(feature = "yaml")]
+    pub fn load_from_yaml<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        fs::load_with(path.as_ref(), Self::from_yaml)
+    }
+
+}
+
+impl PartialEq for Contest {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Contest {}
+
+impl Hash for Contest {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl Default for Contest {
+    /// A placeholder single-winner plurality contest with ID `0` and no
+    /// choices, e.g. for structs that derive [Default]. Any tally on it
+    /// produces an empty result.
+    ///
+    /// Unlike [ContestBuilder::build], the creation time is left at the Unix
+    /// epoch so that all default contests are identical.
+    fn default() -> Self {
+        Contest{
+            id: ContestId(0),
+            description: String::new(),
+            tally_type: PluralityAtLarge.name().to_string(),
+            num_winners: 1,
+            min_choices: 1,
+            max_choices: 1,
+            choices: vec![],
+            quorum: None,
+            allows_write_ins: false,
+            phase: ContestPhase::default(),
+            created_at: DateTime::default(),
+            eligible_voters: None,
+            disqualified: vec![],
+            schema_version: SCHEMA_VERSION,
+            method: None,
+        }
     }
+}
 
+impl fmt::Display for Contest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Contest #{}: {} ({} choices, {} winners)",
+            self.id, self.description, self.choices.len(), self.num_winners)
+    }
 }
 
 /// Factory to create and configure all properties of a new [Contest]
-#[derive(Default)]
 pub struct ContestBuilder {
-    id: i64,
+    id: ContestId,
     description: String,
     tally_type: String,
     num_winners: i64,
     min_choices: i64,
     max_choices: i64,
     choices: Vec<ContestChoice>,
+    quorum: Option<u64>,
+    allows_write_ins: bool,
+    phase: ContestPhase,
+    eligible_voters: Option<u64>,
+    method: Option<EmbeddedMethod>,
+}
+
+impl Default for ContestBuilder {
+    /// A single-winner plurality contest with a random ID, no choices and
+    /// one choice per vote, as [Contest::default]
+    fn default() -> Self {
+        ContestBuilder{
+            id: ContestId(rand::thread_rng().gen_range(0..1_000_000)),
+            description: String::new(),
+            tally_type: PluralityAtLarge.name().to_string(),
+            num_winners: 1,
+            min_choices: 1,
+            max_choices: 1,
+            choices: vec![],
+            quorum: None,
+            allows_write_ins: false,
+            phase: ContestPhase::default(),
+            eligible_voters: None,
+            method: None,
+        }
+    }
 }
 
 impl ContestBuilder {
 
     pub fn new(num_winners: i64, choices: &[ContestChoice]) -> ContestBuilder {
         ContestBuilder{
-            id: rand::thread_rng().gen_range(0..1_000_000),
             num_winners,
             choices: choices.to_owned(),
-            tally_type: "plurality-at-large".to_string(),
             ..Default::default()
         }
     }
 
+    /// Starts from all the settings of `contest`, to build a modified copy.
+    /// 
+    /// The new contest gets a random ID unless [ContestBuilder::custom_id] is called.
+    pub fn from_existing(contest: &Contest) -> ContestBuilder {
+        ContestBuilder{
+            description: contest.description.clone(),
+            tally_type: contest.tally_type.clone(),
+            num_winners: contest.num_winners,
+            min_choices: contest.min_choices,
+            max_choices: contest.max_choices,
+            choices: contest.choices.clone(),
+            quorum: contest.quorum,
+            allows_write_ins: contest.allows_write_ins,
+            phase: contest.phase,
+            eligible_voters: contest.eligible_voters,
+            method: contest.method.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds a single choice to the [Contest]
+    /// 
+    /// ```
+    /// use votes::{ContestBuilder, ContestChoice};
+    /// 
+    /// let contest = ContestBuilder::default()
+    ///     .add_choice(ContestChoice::new(1, "Alice")
+    ///         .with_url("https://alice.example.com")
+    ///         .with_url("https://twitter.com/alice"))
+    ///     .add_choice(ContestChoice::new(2, "Bob"))
+    ///     .build();
+    /// 
+    /// assert_eq!(2, contest.choices()[0].urls.len());
+    /// ```
+    pub fn add_choice(mut self, choice: ContestChoice) -> ContestBuilder {
+        self.choices.push(choice);
+        self
+    }
+
     /// Set a custom [Contest] ID
-    pub fn custom_id(mut self, id: i64) -> ContestBuilder {
-        self.id = id;
+    pub fn custom_id(mut self, id: impl Into<ContestId>) -> ContestBuilder {
+        self.id = id.into();
         self
     }
 
@@ -98,6 +665,14 @@ impl ContestBuilder {
         self
     }
 
+    /// Sets the [TallyMethod] used to count votes for the [Contest], see
+    /// [Contest::tally_method]
+    pub fn tally_method<M: TallyMethod + Send + Sync + 'static>(mut self, method: M) -> ContestBuilder {
+        self.tally_type = method.name().to_owned();
+        self.method = Some(EmbeddedMethod(Arc::new(method)));
+        self
+    }
+
     /// Sets the minimum number of choices per vote
     pub fn min_choices(mut self, choices: i64) -> ContestBuilder {
         self.min_choices = choices;
@@ -110,8 +685,59 @@ impl ContestBuilder {
         self
     }
 
+    /// Sets the minimum number of valid votes required for the result to be binding
+    pub fn quorum(mut self, quorum: u64) -> ContestBuilder {
+        self.quorum = Some(quorum);
+        self
+    }
+
+    /// Sets whether voters can write in a candidate that is not on the ballot
+    pub fn allows_write_ins(mut self, allowed: bool) -> ContestBuilder {
+        self.allows_write_ins = allowed;
+        self
+    }
+
+    /// Sets the number of voters eligible to vote, used to report turnout
+    pub fn eligible_voters(mut self, voters: u64) -> ContestBuilder {
+        self.eligible_voters = Some(voters);
+        self
+    }
+
+    /// Sets the lifecycle phase of the [Contest]
+    pub fn phase(mut self, phase: ContestPhase) -> ContestBuilder {
+        self.phase = phase;
+        self
+    }
+
+    /// Checks the contest being built, returning every invariant that
+    /// currently doesn't hold. The builder can still be changed afterwards.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = vec![];
+        if self.num_winners < 1 {
+            errors.push(ValidationError::NumWinnersZero);
+        }
+        if self.min_choices > self.max_choices {
+            errors.push(ValidationError::MinExceedsMax);
+        }
+        if self.choices.is_empty() {
+            errors.push(ValidationError::EmptyChoices);
+        } else if self.num_winners > self.choices.len() as i64 {
+            errors.push(ValidationError::NumWinnersExceedsChoices);
+        }
+        if let Some(id) = find_duplicate_id(&self.choices) {
+            errors.push(ValidationError::DuplicateChoiceId(id));
+        }
+        errors
+    }
+
     /// Builds the [Contest]
+    /// 
+    /// Panics if two choices share the same ID. Use
+    /// [ContestBuilder::build_validated] to get an [Error] instead.
     pub fn build(self) -> Contest {
+        if let Some(id) = find_duplicate_id(&self.choices) {
+            panic!("Duplicate choice ID {} in contest", id);
+        }
         Contest{
             id: self.id,
             description: self.description,
@@ -120,19 +746,49 @@ impl ContestBuilder {
             min_choices: self.min_choices,
             max_choices: self.max_choices,
             choices: self.choices,
+            quorum: self.quorum,
+            allows_write_ins: self.allows_write_ins,
+            phase: self.phase,
+            created_at: Utc::now(),
+            eligible_voters: self.eligible_voters,
+            disqualified: vec![],
+            schema_version: SCHEMA_VERSION,
+            method: self.method,
+        }
+    }
+
+    /// Builds the [Contest], returning an error if two choices share the same ID
+    pub fn build_validated(self) -> Result<Contest, Error> {
+        if let Some(id) = find_duplicate_id(&self.choices) {
+            return Err(Error::DuplicateChoiceId(id));
         }
+        Ok(self.build())
     }
 
+}
 
+/// Returns the first choice ID that appears more than once, if any
+fn find_duplicate_id(choices: &[ContestChoice]) -> Option<i64> {
+    let mut ids = HashSet::new();
+    choices.iter().map(|c| c.id).find(|id| !ids.insert(*id))
 }
 
 
 /// A choice for a [Contest]
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// 
+/// Like [Contest], hashing only takes the choice ID into account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ContestChoice {
     pub id: i64,
     pub text: String,
     pub urls: Vec<String>,
+    /// Position of this choice when displaying the ballot
+    #[serde(default)]
+    pub display_order: u32,
+    /// Group this choice belongs to on the ballot (e.g. a party)
+    #[serde(default)]
+    pub category: Option<String>,
 }
 
 impl ContestChoice {
@@ -143,47 +799,210 @@ impl ContestChoice {
             id,
             text: text.to_string(),
             urls: vec![],
+            display_order: 0,
+            category: None,
         }
     }
 
+    /// Sets the position of this `ContestChoice` when displaying the ballot
+    pub fn with_display_order(mut self, order: u32) -> Self {
+        self.display_order = order;
+        self
+    }
+
+    /// Sets the group this `ContestChoice` belongs to on the ballot
+    pub fn with_category(mut self, category: &str) -> Self {
+        self.category = Some(category.to_owned());
+        self
+    }
+
+    /// The synthetic `ContestChoice` under which all write-in votes are aggregated
+    pub fn write_in() -> Self {
+        Self::new(WRITE_IN_ID, "(Write-in)")
+    }
+
+    /// Whether this is the synthetic write-in choice
+    pub fn is_write_in(&self) -> bool {
+        self.id == WRITE_IN_ID
+    }
+
     /// Add a URL to this `ContestChoice`
     pub fn add_url(&mut self, url: &str) {
         self.urls.push(url.to_string());
     }
 
+    /// Same as [ContestChoice::add_url], returning the `ContestChoice`
+    pub fn with_url(mut self, url: &str) -> Self {
+        self.add_url(url);
+        self
+    }
+
+    /// The main URL of this choice (e.g. a profile page), i.e. the first one
+    pub fn primary_url(&self) -> Option<&str> {
+        self.urls.first().map(String::as_str)
+    }
+
+    /// Makes `url` the primary URL, keeping the existing ones after it
+    pub fn with_primary_url(mut self, url: &str) -> Self {
+        self.urls.insert(0, url.to_string());
+        self
+    }
+
+    /// All URLs but the primary one
+    pub fn secondary_urls(&self) -> &[String] {
+        self.urls.get(1..).unwrap_or_default()
+    }
+
+}
+
+impl Hash for ContestChoice {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl fmt::Display for ContestChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.id, self.text)
+    }
+}
+
+impl From<(i64, &str)> for ContestChoice {
+    fn from((id, text): (i64, &str)) -> Self {
+        Self::new(id, text)
+    }
+}
+
+impl From<(i64, String)> for ContestChoice {
+    fn from((id, text): (i64, String)) -> Self {
+        Self{
+            text,
+            ..Self::new(id, "")
+        }
+    }
+}
+
+impl From<ContestChoice> for (i64, String) {
+    fn from(value: ContestChoice) -> Self {
+        (value.id, value.text)
+    }
+}
+
+/// Why a [DecodedContestVote] is invalid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InvalidReason {
+    /// Fewer choices than the contest minimum
+    Undervote,
+    /// More choices than the contest maximum
+    Overvote,
+    /// The vote was invalidated with [DecodedContestVote::invalidate]
+    ExplicitlyInvalidated,
+    /// A choice with this ID is not part of the contest, or is a write-in
+    /// when the contest doesn't allow them
+    ChoiceNotInContest(i64),
+}
+
+/// Whether a [DecodedContestVote] is valid, and if not, why
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum VoteValidity {
+    Valid,
+    Invalid(InvalidReason),
 }
 
 /// A vote for a [Contest]. It can include many choices.
 #[derive(Clone,  Deserialize)]
+#[serde(from = "DecodedContestVoteRepr")]
 pub struct DecodedContestVote {
-    /// Indicates that this vote is invalid
-    pub is_explicit_invalid: bool,
+    /// Whether this vote is valid
+    pub validity: VoteValidity,
     /// The choices submitted within this vote
     pub choices: Vec<DecodedVoteChoice>,
     /// The [Contest] for which this vote was emitted
     pub contest: Contest,
 }
 
+/// Votes written before [VoteValidity] only have an `is_explicit_invalid`
+/// flag, their validity is worked out again when they are read
+#[derive(Deserialize)]
+struct DecodedContestVoteRepr {
+    #[serde(default)]
+    validity: Option<VoteValidity>,
+    #[serde(default)]
+    is_explicit_invalid: Option<bool>,
+    choices: Vec<DecodedVoteChoice>,
+    contest: Contest,
+}
+
+impl From<DecodedContestVoteRepr> for DecodedContestVote {
+    fn from(value: DecodedContestVoteRepr) -> Self {
+        let validity = match (value.validity, value.is_explicit_invalid) {
+            (Some(validity), _) => validity,
+            (None, Some(true)) => VoteValidity::Invalid(InvalidReason::ExplicitlyInvalidated),
+            (None, _) => Self::check(&value.contest, &value.choices),
+        };
+        Self{
+            validity,
+            choices: value.choices,
+            contest: value.contest,
+        }
+    }
+}
+
 impl DecodedContestVote {
 
+    /// Builds a vote for `contest`, checking its validity with [DecodedContestVote::check]
     pub fn new(contest: &Contest, choices: Vec<DecodedVoteChoice>) -> Self {
-        let is_valid = Self::is_valid(contest, &choices);
         Self{
+            validity: Self::check(contest, &choices),
             choices,
             contest: contest.clone(),
-            is_explicit_invalid: !is_valid,
         }
     }
 
     /// Explicitly invalidates this vote
     pub fn invalidate(&mut self) {
-        self.is_explicit_invalid = true;
+        self.validity = VoteValidity::Invalid(InvalidReason::ExplicitlyInvalidated);
+    }
+
+    /// Whether this vote is invalid, for any reason
+    pub fn is_invalid(&self) -> bool {
+        self.validity != VoteValidity::Valid
+    }
+
+    /// Why this vote is invalid, `None` if it is valid
+    pub fn reason(&self) -> Option<InvalidReason> {
+        match self.validity {
+            VoteValidity::Valid => None,
+            VoteValidity::Invalid(reason) => Some(reason),
+        }
+    }
+
+    /// Whether this vote's choices are valid according to its contest rules.
+    /// 
+    /// Explicit invalidation is not taken into account.
+    pub fn is_valid_vote(&self) -> bool {
+        Self::validate(&self.contest, &self.choices)
+    }
+
+    /// Determines whether a vote with `choices` would be valid according to
+    /// contest rules, before building it
+    pub fn validate(contest: &Contest, choices: &[DecodedVoteChoice]) -> bool {
+        Self::check(contest, choices) == VoteValidity::Valid
     }
 
-    /// Determines whether a vote is valid or not according to contest rules
-    pub fn is_valid(contest: &Contest, choices: &[DecodedVoteChoice]) -> bool {
+    /// Same as [DecodedContestVote::validate], but tells why the vote would
+    /// be invalid. Unknown choices are reported before the number of choices.
+    pub fn check(contest: &Contest, choices: &[DecodedVoteChoice]) -> VoteValidity {
         let count = choices.len() as i64;
-        count <= contest.max_choices && count >= contest.min_choices
+        if let Some(choice) = choices.iter().find(|c| !contest.is_choice_valid(&c.contest_choice)) {
+            VoteValidity::Invalid(InvalidReason::ChoiceNotInContest(choice.contest_choice.id))
+        } else if count > contest.max_choices {
+            VoteValidity::Invalid(InvalidReason::Overvote)
+        } else if count < contest.min_choices {
+            VoteValidity::Invalid(InvalidReason::Undervote)
+        } else {
+            VoteValidity::Valid
+        }
     }
 
 }
@@ -193,7 +1012,7 @@ impl Serialize for DecodedContestVote {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where S: serde::Serializer {
         let mut state = serializer.serialize_struct("DecodedContestVote", 3)?;
-        state.serialize_field("is_explicit_invalid", &self.is_explicit_invalid)?;
+        state.serialize_field("validity", &self.validity)?;
         state.serialize_field("choices", &self.choices)?;
         state.serialize_field("contest", &self.contest.id)?;
         state.end()
@@ -203,11 +1022,20 @@ impl Serialize for DecodedContestVote {
 
 /// A choice with the number of votes assigned to that choice
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct DecodedVoteChoice {
     /// The choice that was made
     pub contest_choice: ContestChoice,
-    /// The number of votes that were assigned
+    /// The number of votes that were assigned, i.e. the weight of this choice.
+    ///
+    /// [Tally::result](crate::Tally::result) adds this value to the choice's
+    /// count, so a choice with `selected: 3` counts as three votes. Zero means
+    /// the choice was not selected. [StvTally](crate::StvTally) reads it as a
+    /// preference rank instead.
     pub selected: u64,
+    /// The name written in by the voter, for write-in choices
+    #[serde(default)]
+    pub write_in_text: Option<String>,
 }
 
 impl DecodedVoteChoice {
@@ -216,6 +1044,24 @@ impl DecodedVoteChoice {
         Self {
             contest_choice: choice,
             selected: 1,
+            write_in_text: None,
+        }
+    }
+
+    /// Create a choice that carries `weight` votes (e.g. a board member with three votes)
+    pub fn with_weight(choice: ContestChoice, weight: u64) -> Self {
+        Self {
+            selected: weight,
+            ..Self::new(choice)
+        }
+    }
+
+    /// Create a vote for a candidate written in by the voter
+    pub fn write_in(text: &str) -> Self {
+        Self {
+            contest_choice: ContestChoice::write_in(),
+            selected: 1,
+            write_in_text: Some(text.to_string()),
         }
     }
 
@@ -0,0 +1,49 @@
+// csv.rs
+
+use crate::Error;
+
+/// Quotes a CSV field if it contains a delimiter, a quote or a line break
+pub(crate) fn escape_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        quote_field(field)
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Unconditionally quotes a CSV field, escaping inner quotes
+pub(crate) fn quote_field(field: &str) -> String {
+    format!("\"{}\"", field.replace('"', "\"\""))
+}
+
+/// Splits a single CSV record into its fields, honouring quoted fields.
+///
+/// Records are expected to fit in a single line.
+pub(crate) fn split_record(line: &str) -> Result<Vec<String>, Error> {
+
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                // Escaped quote inside a quoted field
+                field.push('"');
+                chars.next();
+            },
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(Error::CSV(format!("Unterminated quoted field in `{}`", line)));
+    }
+    fields.push(field);
+    Ok(fields)
+
+}
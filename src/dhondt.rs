@@ -0,0 +1,99 @@
+// dhondt.rs
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::Tally;
+
+/// D'Hondt proportional representation for party-list contests.
+///
+/// Every choice belongs to a party and the votes of all its choices are added
+/// up. Seats are then allocated one at a time to the party with the highest
+/// quotient `votes / (seats_won + 1)`.
+#[derive(Debug, PartialEq)]
+pub struct DHondtTally {
+    tally: Tally,
+    party_of: HashMap<i64, String>,
+}
+
+/// The result of a [DHondtTally]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DHondtResult {
+    /// Seats won by every party, including those that won none
+    pub seats_by_party: HashMap<String, u32>,
+    /// Every allocation step, in order
+    pub rounds: Vec<DHondtRound>,
+}
+
+/// A single seat allocation of a [DHondtTally]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DHondtRound {
+    /// The seat being allocated, starting at 1
+    pub seat: u32,
+    /// The party that won the seat
+    pub party: String,
+    /// The quotient with which the party won the seat
+    pub quotient: f64,
+}
+
+impl DHondtTally {
+
+    /// `party_of` maps choice IDs to party names. Votes for choices without
+    /// a party are not counted.
+    pub fn new(tally: Tally, party_of: HashMap<i64, String>) -> Self {
+        Self{ tally, party_of }
+    }
+
+    /// The underlying [Tally]
+    pub fn tally(&self) -> &Tally {
+        &self.tally
+    }
+
+    /// Total votes for every party
+    pub fn party_votes(&self) -> HashMap<String, u64> {
+        let mut votes: HashMap<String, u64> = self.party_of.values()
+            .map(|party| (party.clone(), 0))
+            .collect();
+        for (choice_id, count) in self.tally.counts_all() {
+            if let Some(party) = self.party_of.get(&choice_id) {
+                *votes.entry(party.clone()).or_default() += count;
+            }
+        }
+        votes
+    }
+
+    /// Allocate `total_seats` among the parties.
+    ///
+    /// Ties on the quotient go to the party with more votes, then by name.
+    pub fn result(&self, total_seats: u32) -> DHondtResult {
+
+        // Sorted so that ties are resolved the same way every time
+        let mut party_votes: Vec<(String, u64)> = self.party_votes().into_iter().collect();
+        party_votes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let mut seats_by_party: HashMap<String, u32> = party_votes.iter()
+            .map(|(party, _)| (party.clone(), 0))
+            .collect();
+        let mut rounds = vec![];
+
+        for seat in 1..=total_seats {
+            let best = party_votes.iter()
+                .filter(|(_, votes)| *votes > 0)
+                .map(|(party, votes)| (party, *votes as f64 / (seats_by_party[party] + 1) as f64))
+                .reduce(|best, next| if next.1 > best.1 { next } else { best });
+            let Some((party, quotient)) = best else {
+                break;
+            };
+            *seats_by_party.get_mut(party).expect("Party should have a seat count") += 1;
+            rounds.push(DHondtRound{ seat, party: party.clone(), quotient });
+        }
+
+        DHondtResult{
+            seats_by_party,
+            rounds,
+        }
+
+    }
+
+}
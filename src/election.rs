@@ -0,0 +1,185 @@
+// election.rs
+
+use std::collections::BTreeMap;
+use std::{fs::File, path::Path};
+use std::io::{BufRead, BufReader, Write};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{fs, Contest, ContestId, ContestResult, DefaultNaming, Error, FileNamingStrategy, FlatVote, Tally};
+
+/// An election made up of several simultaneous [Contest]s
+/// (e.g. mayor, city council and ballot measures).
+///
+/// Votes for every contest are kept in an [ElectionTally].
+#[derive(Debug, PartialEq)]
+pub struct Election {
+    id: i64,
+    contests: Vec<Contest>,
+    tally: ElectionTally,
+}
+
+/// On-disk representation of an [Election], with contests keyed by their IDs
+#[derive(Serialize, Deserialize)]
+struct ElectionFile {
+    id: i64,
+    contests: BTreeMap<ContestId, Contest>,
+}
+
+impl Election {
+
+    /// Create a new [Election] with a random ID for the given `contests`
+    pub fn new(contests: &[Contest]) -> Self {
+        let id = rand::thread_rng().gen_range(0..1_000_000);
+        Self{
+            id,
+            contests: contests.to_owned(),
+            tally: ElectionTally::new(id, contests),
+        }
+    }
+
+    /// The election ID
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    /// All contests in this election
+    pub fn contests(&self) -> &[Contest] {
+        &self.contests
+    }
+
+    /// The votes submitted for this election
+    pub fn tally(&self) -> &ElectionTally {
+        &self.tally
+    }
+
+    /// Replaces all votes with the ones in `tally`
+    pub fn with_tally(self, tally: ElectionTally) -> Self {
+        Self{
+            tally,
+            ..self
+        }
+    }
+
+    /// Adds a single vote to the tally of the contest it belongs to
+    pub fn add_vote(&mut self, vote: FlatVote) {
+        self.tally.add_vote(vote);
+    }
+
+    /// The [Tally] for the contest with ID `contest_id`
+    pub fn tally_for(&self, contest_id: ContestId) -> Option<&Tally> {
+        self.tally.tally_for(contest_id)
+    }
+
+    /// The result for the contest with ID `contest_id`
+    pub fn result_for(&self, contest_id: ContestId) -> Option<ContestResult> {
+        self.tally_for(contest_id).map(Tally::result)
+    }
+
+    /// The results for every contest, in the same order as [Election::contests]
+    pub fn all_results(&self) -> Vec<ContestResult> {
+        self.tally.tallies.iter().map(Tally::result).collect()
+    }
+
+    /// Saves all contests to a JSON-encoded file and returns the filename.
+    ///
+    /// Votes are not included, use [ElectionTally::save_to_file] for those.
+    pub fn save_to_file(&self) -> Result<String, Error> {
+        self.save_to_file_with(&DefaultNaming)
+    }
+
+    /// Same as [Election::save_to_file], with the file named by `naming`
+    pub fn save_to_file_with(&self, naming: &dyn FileNamingStrategy) -> Result<String, Error> {
+        let fname = naming.file_name("election", self.id);
+        let data = ElectionFile{
+            id: self.id,
+            contests: self.contests.iter().map(|c| (c.id(), c.clone())).collect(),
+        };
+        let serialized = serde_json::to_string_pretty(&data)?;
+        fs::write_atomically(&fname, |file| Ok(file.write_all(serialized.as_bytes())?))?;
+        Ok(fname)
+    }
+
+    /// Loads an election from a JSON file. The loaded election has no votes.
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let (id, contests) = fs::load_with(path.as_ref(), |contents| {
+            let data: ElectionFile = serde_json::from_str(contents)?;
+            let contests = data.contests.into_values()
+                .map(Contest::migrate)
+                .collect::<Result<Vec<_>, Error>>()?;
+            Ok((data.id, contests))
+        })?;
+        Ok(Self{
+            id,
+            tally: ElectionTally::new(id, &contests),
+            contests,
+        })
+    }
+
+}
+
+/// Vote tallying for all contests of an [Election], with one [Tally] per [Contest]
+#[derive(Debug, PartialEq)]
+pub struct ElectionTally {
+    election: i64,
+    tallies: Vec<Tally>,
+}
+
+impl ElectionTally {
+
+    fn new(election: i64, contests: &[Contest]) -> Self {
+        Self{
+            election,
+            tallies: contests.iter().map(Tally::new).collect(),
+        }
+    }
+
+    /// The [Tally] for the contest with ID `contest_id`
+    pub fn tally_for(&self, contest_id: ContestId) -> Option<&Tally> {
+        self.tallies.iter().find(|t| t.contest().id() == contest_id)
+    }
+
+    /// Adds a single vote to the tally of the contest it belongs to.
+    /// Votes for contests that are not part of the election are discarded.
+    pub fn add_vote(&mut self, vote: FlatVote) {
+        if let Some(tally) = self.tallies.iter_mut().find(|t| t.contest().id() == vote.contest_id()) {
+            tally.add_vote(vote);
+        }
+    }
+
+    /// Saves the votes for all contests to a single file and returns the filename
+    pub fn save_to_file(&self) -> Result<String, Error> {
+        self.save_to_file_with(&DefaultNaming)
+    }
+
+    /// Same as [ElectionTally::save_to_file], with the file named by `naming`
+    pub fn save_to_file_with(&self, naming: &dyn FileNamingStrategy) -> Result<String, Error> {
+        let fname = naming.file_name("election-votes", self.election);
+        fs::write_atomically(&fname, |file| {
+            for v in self.tallies.iter().flat_map(Tally::votes) {
+                let serialized: String = serde_json::to_string(v)?;
+                file.write_all(serialized.as_bytes())?;
+                file.write_all("\n".as_bytes())?;
+            }
+            Ok(())
+        })?;
+        Ok(fname)
+    }
+
+    /// Loads the votes for all contests in `election` from a file
+    pub fn load_from_file<P: AsRef<Path>>(path: P, election: &Election) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let read = || -> Result<Self, Error> {
+            let reader = BufReader::new(File::open(path)?);
+            let mut tally = Self::new(election.id, &election.contests);
+            for line in reader.lines().map_while(Result::ok) {
+                let vote = serde_json::from_str::<FlatVote>(&line)?.migrate()?;
+                tally.add_vote(vote);
+            }
+            Ok(tally)
+        };
+        read().map_err(|e| e.context(format!("Failed to read `{}`", path.display())))
+    }
+
+}
@@ -1,6 +1,8 @@
 // error.rs
 
-use std::io;
+use std::{fmt, io};
+
+use crate::ContestId;
 
 /// Errors returned by this library
 #[derive(Debug)]
@@ -9,6 +11,121 @@ pub enum Error {
     IO(io::Error),
     /// Decoding errors when processing input files
     JSON(serde_json::Error),
+    /// A file was written but could not be moved into its final location
+    AtomicWriteFailed(io::Error),
+    /// Encoding and decoding errors for binary vote files
+    #[cfg(feature = "bincode")]
+    Bincode(bincode::Error),
+    /// Decoding errors when processing CSV input files
+    CSV(String),
+    /// Encoding and decoding errors for TOML contest files
+    #[cfg(feature = "toml")]
+    TOML(String),
+    /// Encoding and decoding errors for YAML contest files
+    #[cfg(feature = "yaml")]
+    YAML(serde_yaml::Error),
+    /// Two choices in the same contest share this ID
+    DuplicateChoiceId(i64),
+    /// A vote references a choice ID that is not part of the contest
+    UnknownChoiceId(i64),
+    /// The checksum stored in a vote file does not match its contents
+    ChecksumMismatch { expected: String, actual: String },
+    /// A file belongs to a different contest than the one it was loaded for
+    ContestMismatch { expected: ContestId, actual: ContestId },
+    /// A vote file loaded in strict mode does not end with a checksum
+    MissingChecksum,
+    /// A vote was submitted for a contest that is not accepting votes
+    ContestNotOpen,
+    /// Choice weights for random vote generation are not usable
+    InvalidWeights(String),
+    /// A file was written with a newer serialization format than this
+    /// library supports, see [SCHEMA_VERSION](crate::SCHEMA_VERSION)
+    UnsupportedSchemaVersion(u32),
+    /// Another error with additional information, e.g. the file involved
+    Context { message: String, source: Box<Error> },
+}
+
+impl Error {
+
+    /// Wraps this error with additional information
+    pub fn context(self, ctx: impl Into<String>) -> Error {
+        Error::Context{
+            message: ctx.into(),
+            source: Box::new(self),
+        }
+    }
+
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IO(e) => write!(f, "I/O error: {}", e),
+            Self::JSON(e) => write!(f, "JSON error: {}", e),
+            Self::AtomicWriteFailed(e) => write!(f, "Failed to move file into place: {}", e),
+            #[cfg(feature = "bincode")]
+            Self::Bincode(e) => write!(f, "Binary encoding error: {}", e),
+            Self::CSV(msg) => write!(f, "CSV error: {}", msg),
+            #[cfg(feature = "toml")]
+            Self::TOML(msg) => write!(f, "TOML error: {}", msg),
+            #[cfg(feature = "yaml")]
+            Self::YAML(e) => write!(f, "YAML error: {}", e),
+            Self::DuplicateChoiceId(id) => write!(f, "Duplicate choice ID {}", id),
+            Self::UnknownChoiceId(id) => write!(f, "Unknown choice ID {}", id),
+            Self::ChecksumMismatch{ expected, actual } =>
+                write!(f, "Checksum mismatch: expected {}, got {}", expected, actual),
+            Self::ContestMismatch{ expected, actual } =>
+                write!(f, "Contest mismatch: expected #{}, got #{}", expected, actual),
+            Self::MissingChecksum => write!(f, "Missing checksum"),
+            Self::ContestNotOpen => write!(f, "Contest is not open for voting"),
+            Self::InvalidWeights(msg) => write!(f, "Invalid weights: {}", msg),
+            Self::UnsupportedSchemaVersion(version) => write!(f, "Unsupported schema version {}", version),
+            Self::Context{ message, source } => write!(f, "{}: {}", message, source),
+        }
+    }
+}
+
+/// An invariant of a [Contest](crate::Contest) that doesn't hold, as
+/// reported by [ContestBuilder::validate](crate::ContestBuilder::validate)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValidationError {
+    /// The contest has no winners
+    NumWinnersZero,
+    /// `min_choices` is greater than `max_choices`
+    MinExceedsMax,
+    /// There are more winners than choices
+    NumWinnersExceedsChoices,
+    /// The contest has no choices
+    EmptyChoices,
+    /// Two choices share the same ID
+    DuplicateChoiceId(i64),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NumWinnersZero => write!(f, "num_winners must be at least 1"),
+            Self::MinExceedsMax => write!(f, "max_choices must be >= min_choices"),
+            Self::NumWinnersExceedsChoices => write!(f, "num_winners must not exceed the number of choices"),
+            Self::EmptyChoices => write!(f, "There must be at least one choice"),
+            Self::DuplicateChoiceId(id) => write!(f, "Duplicate choice ID {}", id),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::IO(e) | Self::AtomicWriteFailed(e) => Some(e),
+            Self::JSON(e) => Some(e),
+            #[cfg(feature = "bincode")]
+            Self::Bincode(e) => Some(e),
+            #[cfg(feature = "yaml")]
+            Self::YAML(e) => Some(e),
+            Self::Context{ source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
 }
 
 impl From<io::Error> for Error {
@@ -21,4 +138,32 @@ impl From<serde_json::Error> for Error {
     fn from(value: serde_json::Error) -> Self {
         Self::JSON(value)
     }
+}
+
+#[cfg(feature = "bincode")]
+impl From<bincode::Error> for Error {
+    fn from(value: bincode::Error) -> Self {
+        Self::Bincode(value)
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<toml::ser::Error> for Error {
+    fn from(value: toml::ser::Error) -> Self {
+        Self::TOML(value.to_string())
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<toml::de::Error> for Error {
+    fn from(value: toml::de::Error) -> Self {
+        Self::TOML(value.to_string())
+    }
+}
+
+#[cfg(feature = "yaml")]
+impl From<serde_yaml::Error> for Error {
+    fn from(value: serde_yaml::Error) -> Self {
+        Self::YAML(value)
+    }
 }
\ No newline at end of file
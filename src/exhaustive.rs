@@ -0,0 +1,97 @@
+// exhaustive.rs
+
+use std::cmp::Reverse;
+
+use serde::Serialize;
+
+use crate::{ContestChoice, ContestResult, Tally};
+
+/// The result of an exhaustive ballot, see [Tally::multi_round_result]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MultiRoundResult {
+    /// The result of every round along with the choice eliminated after it
+    pub rounds: Vec<(ContestResult, ContestChoice)>,
+    /// The most voted choice once no more choices had to be eliminated
+    pub winner: ContestChoice,
+}
+
+impl Tally {
+
+    /// Removes the last-placed choice and returns a new [Tally] for the
+    /// remaining ones, along with the eliminated choice.
+    ///
+    /// The new contest is the same but for the eliminated choice. Votes for the
+    /// eliminated choice lose it, and votes left without any choice are kept
+    /// as exhausted ballots, so that the total of valid votes doesn't change
+    /// between rounds. Ties for last place eliminate the choice with the
+    /// highest ID, the one ranked last in [ContestResult::results].
+    ///
+    /// Panics if the contest has no choices.
+    pub fn eliminate_last(&self) -> (Tally, ContestChoice) {
+        self.eliminate_last_from(&self.result())
+    }
+
+    /// Exhaustive ballot: counts the votes and eliminates the last-placed
+    /// choice, round after round, until only as many choices as winners are
+    /// left. See [Tally::eliminate_last].
+    ///
+    /// The result of every round reports the ballots exhausted so far in
+    /// [ContestResult::exhausted_ballots].
+    ///
+    /// Panics if the contest has no choices.
+    pub fn multi_round_result(&self) -> MultiRoundResult {
+        let mut tally = self.clone();
+        let mut rounds = vec![];
+        while tally.contest().num_choices() > tally.contest().num_winners().max(1) as usize {
+            let mut result = tally.result();
+            result.exhausted_ballots = self.exhausted_in(&tally);
+            let (next, eliminated) = tally.eliminate_last_from(&result);
+            rounds.push((result, eliminated));
+            tally = next;
+        }
+        let winner = tally.result().winners.first().cloned()
+            .or_else(|| tally.contest().choices().first().cloned())
+            .expect("Can't hold an exhaustive ballot without choices");
+        MultiRoundResult{
+            rounds,
+            winner,
+        }
+    }
+
+    /// Same as [Tally::eliminate_last], with `result` as the current count
+    fn eliminate_last_from(&self, result: &ContestResult) -> (Tally, ContestChoice) {
+        let contest = self.contest();
+        let eliminated = contest.choices().iter()
+            .min_by_key(|c| {
+                let count = result.choice_result_for(c.id).map_or(0, |r| r.total_count);
+                (count, Reverse(c.id))
+            })
+            .expect("Can't eliminate a choice from a contest without choices")
+            .clone();
+
+        let mut next = contest.clone();
+        next.remove_choice(eliminated.id);
+
+        let votes = self.votes().iter()
+            .cloned()
+            .map(|mut v| {
+                v.remove_choice_id(eliminated.id);
+                v
+            })
+            .collect();
+        (Tally::new(&next).with_votes(votes), eliminated)
+    }
+
+    /// Number of counted ballots that had choices in this tally but none left
+    /// in `round`, a later round of [Tally::multi_round_result]
+    fn exhausted_in(&self, round: &Tally) -> u64 {
+        self.votes().iter()
+            .zip(round.votes())
+            .filter(|(original, current)| {
+                !original.is_invalid() && !original.is_provisional()
+                    && original.choice_count() > 0 && current.choice_count() == 0
+            })
+            .count() as u64
+    }
+
+}
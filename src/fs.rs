@@ -0,0 +1,95 @@
+// fs.rs
+
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+
+use crate::Error;
+
+/// Writes `fname` atomically.
+/// 
+/// Data is written to `{fname}.tmp` first, which then gets renamed to
+/// `fname` once flushed. On most filesystems the rename is atomic, so a
+/// crash mid-write never leaves a truncated file behind.
+/// 
+/// Errors carry the filename as context.
+pub(crate) fn write_atomically<P, F>(fname: P, write: F) -> Result<(), Error>
+where P: AsRef<Path>, F: FnOnce(&mut File) -> Result<(), Error> {
+    let fname = fname.as_ref();
+    let mut tmp = fname.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let result = File::create(&tmp).map_err(Error::from).and_then(|mut file| {
+        if let Err(e) = write(&mut file).and_then(|_| Ok(file.flush()?)) {
+            // Don't leave a partial temp file around
+            let _ = fs::remove_file(&tmp);
+            return Err(e);
+        }
+        fs::rename(&tmp, fname).map_err(Error::AtomicWriteFailed)
+    });
+    result.map_err(|e| e.context(format!("Failed to write `{}`", fname.display())))
+}
+
+/// Reads and decodes a JSON file, with the filename as context on errors
+pub(crate) fn load_json<T: DeserializeOwned>(path: &Path) -> Result<T, Error> {
+    load_with(path, |contents| Ok(serde_json::from_str(contents)?))
+}
+
+/// Reads a whole file and decodes it with `decode`, with the filename as
+/// context on errors
+pub(crate) fn load_with<T, F>(path: &Path, decode: F) -> Result<T, Error>
+where F: FnOnce(&str) -> Result<T, Error> {
+    let read = || -> Result<T, Error> {
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        decode(&contents)
+    };
+    read().map_err(|e| e.context(format!("Failed to read `{}`", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// A file name in the temp directory, unique to this process
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("votes-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn write_atomically_replaces_file() {
+
+        let path = temp_path("atomic.txt");
+        fs::write(&path, "old").expect("Failed to write file");
+
+        write_atomically(&path, |file| Ok(file.write_all(b"new")?))
+            .expect("Failed to write file atomically");
+        assert_eq!("new", fs::read_to_string(&path).expect("Failed to read file"));
+        assert!(!Path::new(&format!("{}.tmp", path.display())).exists());
+
+        fs::remove_file(&path).expect("Failed to remove file after test");
+
+    }
+
+    #[test]
+    fn write_atomically_cleans_up_on_error() {
+
+        let path = temp_path("failed.txt");
+        fs::write(&path, "old").expect("Failed to write file");
+
+        // The original file is left untouched, and so is the filesystem
+        let result = write_atomically(&path, |file| {
+            file.write_all(b"partial")?;
+            Err(Error::CSV("Failed halfway".to_owned()))
+        });
+        assert!(matches!(result, Err(Error::Context{ source, .. }) if matches!(*source, Error::CSV(_))));
+        assert_eq!("old", fs::read_to_string(&path).expect("Failed to read file"));
+        assert!(!Path::new(&format!("{}.tmp", path.display())).exists());
+
+        fs::remove_file(&path).expect("Failed to remove file after test");
+
+    }
+
+}
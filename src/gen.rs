@@ -3,8 +3,10 @@
 use std::collections::HashSet;
 
 use rand::Rng;
+use rand::seq::SliceRandom;
 
 use crate::tally::FlatVote; 
+use crate::Error;
 use crate::contest::{Contest, ContestBuilder, ContestChoice, DecodedContestVote, DecodedVoteChoice};
 
 static NAMES: [&str; 100] = ["Alexander", "Olivia", "William", "Emma", "Ethan", "Sophia", "Benjamin", "Isabella", "James", "Mia", "Michael", "Charlotte", "Daniel", "Amelia", "Matthew", "Harper", "Jackson", "Evelyn", "David", "Abigail", "Joseph", "Emily", "Samuel", "Elizabeth", "Henry", "Avery", "Christopher", "Sofia", "Andrew", "Ella", "Lucas", "Scarlett", "Gabriel", "Grace", "Joshua", "Lily", "John", "Chloe", "Isaac", "Zoey", "Nathan", "Madison", "Oliver", "Aria", "Dylan", "Riley", "Elijah", "Layla", "Caleb", "Penelope", "Anthony", "Victoria", "Mason", "Natalie", "Logan", "Lucy", "Aaron", "Nora", "Jack", "Lillian", "Jonathan", "Hannah", "Ryan", "Addison", "Nicholas", "Eleanor", "Adam", "Aubrey", "Zachary", "Stella", "Levi", "Savannah", "Aiden", "Brooklyn", "Julian", "Claire", "Christian", "Violet", "Brayden", "Skylar", "Samuel", "Paisley", "Xavier", "Audrey", "Cameron", "Leah", "Connor", "Sadie", "Jeremiah", "Ariana", "Hunter", "Allison", "Thomas", "Sarah", "Charles", "Caroline", "Eli", "Naomi", "Jordan", "Katherine"];
@@ -14,6 +16,12 @@ static NAMES: [&str; 100] = ["Alexander", "Olivia", "William", "Emma", "Ethan",
 /// `max_choices` and `min_choices` will be randomly generated according to
 /// the specified `num_winners`.
 pub fn gen_random_contest(num_winners: i64, choices: Vec<ContestChoice>) -> Contest {
+    gen_random_contest_builder(num_winners, choices).build()
+}
+
+/// Same as [gen_random_contest], but returns the [ContestBuilder] so that
+/// further settings can be changed before building the [Contest]
+pub fn gen_random_contest_builder(num_winners: i64, choices: Vec<ContestChoice>) -> ContestBuilder {
 
     let max_choices = match num_winners {
         1 => 1,
@@ -28,16 +36,28 @@ pub fn gen_random_contest(num_winners: i64, choices: Vec<ContestChoice>) -> Cont
         .description("A random contest")
         .max_choices(max_choices)
         .min_choices(min_choices)
-        .build()
 
 }
 
 /// Generate `count` random choices for a [Contest]
 /// - ID's for choices will be randomly generated integers in the range `0..1000`
-/// and are guaranteed to be unique on every generated set.
+///   and are guaranteed to be unique on every generated set.
 /// - Names for the choices are chosen from a basket of 100 names and are not
-/// guaranteed to be unique.
+///   guaranteed to be unique.
+/// 
+/// Panics if `count` exceeds 1000, as there wouldn't be enough unique IDs.
 pub fn gen_random_choices(count: usize) -> Vec<ContestChoice> {
+    gen_random_choices_from(count, &NAMES)
+}
+
+/// Same as [gen_random_choices], with the names of the choices drawn from
+/// `names` (e.g. city names for a more descriptive test)
+/// 
+/// Panics if `count` exceeds 1000 or if `names` is empty.
+pub fn gen_random_choices_from(count: usize, names: &[&str]) -> Vec<ContestChoice> {
+
+    assert!(count <= 1_000, "Cannot generate more than 1000 unique choice IDs");
+    assert!(!names.is_empty(), "Cannot generate choices without names");
 
     let mut ids: HashSet<i64> = HashSet::new();
     (0..count).map(|_| {
@@ -48,7 +68,8 @@ pub fn gen_random_choices(count: usize) -> Vec<ContestChoice> {
         }
         ids.insert(id);
         // Use a random name (could be repeated)
-        let name = NAMES[rand::thread_rng().gen_range(1..100)];
+        let name = names.choose(&mut rand::thread_rng())
+            .expect("Names can't be empty");
         // Create a `ContestChoice`
         ContestChoice::new(id, name)
     }).collect()
@@ -87,3 +108,115 @@ pub fn gen_random_votes(count: usize, contest: &Contest) -> Vec<FlatVote> {
     }).collect()
     
 }
+
+/// Same as [gen_random_votes], with every vote coming from a source picked
+/// at random out of `sources` (see [FlatVote::source]). Votes have no source
+/// if `sources` is empty.
+pub fn gen_random_votes_with_sources(count: usize, contest: &Contest, sources: &[&str]) -> Vec<FlatVote> {
+    let mut rng = rand::thread_rng();
+    gen_random_votes(count, contest).into_iter()
+        .map(|vote| match sources.choose(&mut rng) {
+            Some(source) => vote.with_source(source),
+            None => vote,
+        })
+        .collect()
+}
+
+/// Generate `count` random votes for `contest`, of which
+/// `(count * invalid_ratio) as usize` are invalid
+/// 
+/// - Invalid votes select fewer than `min_choices` or more than `max_choices`
+///   choices. If the contest leaves no room for either, they are explicitly
+///   invalidated instead.
+/// - Valid votes select between `min_choices` and `max_choices` choices
+/// 
+/// Panics if `invalid_ratio` is outside `[0.0, 1.0]`.
+pub fn gen_random_votes_with_invalid_ratio(count: usize, contest: &Contest, invalid_ratio: f64) -> Vec<FlatVote> {
+
+    assert!((0.0..=1.0).contains(&invalid_ratio), "invalid_ratio must be within [0.0, 1.0]");
+
+    let num_invalid = (count as f64 * invalid_ratio) as usize;
+    let available = contest.choices().len();
+    let min_choices = (contest.min_choices().max(0) as usize).min(available);
+    let max_choices = (contest.max_choices().max(0) as usize).min(available);
+    let mut rng = rand::thread_rng();
+
+    let mut votes: Vec<FlatVote> = (0..count).map(|i| {
+
+        // Undervotes and overvotes that fit within the available choices
+        let mut invalid_counts = vec![];
+        if i < num_invalid {
+            if min_choices > 0 {
+                invalid_counts.push(rng.gen_range(0..min_choices));
+            }
+            if max_choices < available {
+                invalid_counts.push(rng.gen_range(max_choices + 1..=available));
+            }
+        }
+
+        let num_choices = match invalid_counts.choose(&mut rng) {
+            Some(n) => *n,
+            None => rng.gen_range(min_choices..=max_choices.max(min_choices)),
+        };
+        let choices = contest.choices()
+            .choose_multiple(&mut rng, num_choices)
+            .map(|c| DecodedVoteChoice::new(c.clone()))
+            .collect();
+
+        let mut vote = DecodedContestVote::new(contest, choices);
+        if i < num_invalid && invalid_counts.is_empty() {
+            vote.invalidate();
+        }
+        vote.into()
+
+    }).collect();
+
+    // Don't keep all invalid votes at the front
+    votes.shuffle(&mut rng);
+    votes
+
+}
+
+/// Generate `count` valid random votes for `contest` where some choices are
+/// more popular than others.
+/// 
+/// `weights` holds the relative probability of each choice in
+/// [Contest::choices] being selected, and is normalized internally. Every
+/// vote selects between `min_choices` and `max_choices` choices, never
+/// picking a choice with zero weight.
+/// 
+/// Fails with [Error::InvalidWeights] if there is not exactly one weight per
+/// choice, if any weight is negative, or if they are all zero.
+pub fn gen_random_votes_biased(count: usize, contest: &Contest, weights: &[f64]) -> Result<Vec<FlatVote>, Error> {
+
+    if weights.len() != contest.choices().len() {
+        return Err(Error::InvalidWeights(format!(
+            "Expected {} weights, got {}", contest.choices().len(), weights.len())));
+    }
+    if let Some(w) = weights.iter().find(|w| **w < 0.0 || w.is_nan()) {
+        return Err(Error::InvalidWeights(format!("Invalid weight {}", w)));
+    }
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return Err(Error::InvalidWeights("All weights are zero".to_string()));
+    }
+
+    let weighted: Vec<(&ContestChoice, f64)> = contest.choices().iter()
+        .zip(weights.iter().map(|w| w / total))
+        .filter(|(_, w)| *w > 0.0)
+        .collect();
+    let min_choices = (contest.min_choices().max(1) as usize).min(weighted.len());
+    let max_choices = (contest.max_choices().max(0) as usize).clamp(min_choices, weighted.len());
+    let mut rng = rand::thread_rng();
+
+    (0..count).map(|_| {
+        let num_choices = rng.gen_range(min_choices..=max_choices);
+        let choices = weighted
+            .choose_multiple_weighted(&mut rng, num_choices, |(_, w)| *w)
+            .map_err(|e| Error::InvalidWeights(e.to_string()))?
+            .map(|(c, _)| DecodedVoteChoice::new((*c).clone()))
+            .collect();
+        Ok(DecodedContestVote::new(contest, choices).into())
+    }).collect()
+
+}
@@ -0,0 +1,152 @@
+// incremental.rs
+
+use std::io::Write;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{fs, Contest, ContestId, ContestResult, Error, FlatVote, PluralityAtLarge, TallyMode, VoteCounts};
+
+/// Plurality counting of votes as they arrive, without keeping them.
+/// 
+/// Only the running count for every choice is kept in memory, so any number
+/// of votes can be fed one at a time. A [ContestResult] can be taken at any
+/// point with [IncrementalTally::snapshot].
+#[derive(Debug, Clone)]
+pub struct IncrementalTally {
+    contest: Contest,
+    counts: VoteCounts,
+}
+
+impl IncrementalTally {
+
+    /// Start counting votes for `contest`
+    pub fn new(contest: &Contest) -> Self {
+        Self{
+            contest: contest.clone(),
+            counts: VoteCounts::default(),
+        }
+    }
+
+    /// The [Contest] being counted
+    pub fn contest(&self) -> &Contest {
+        &self.contest
+    }
+
+    /// Counts a single vote.
+    /// 
+    /// As with [Tally::result](crate::Tally::result), provisional votes are
+    /// only reported and votes including a disqualified choice are left out.
+    /// Votes for other contests are ignored.
+    pub fn feed(&mut self, vote: &FlatVote) {
+        if vote.contest_id() != self.contest.id() {
+            return;
+        }
+        let disqualified = self.contest.disqualified_choice_ids();
+        if disqualified.iter().any(|id| vote.contains_choice_id(*id)) {
+            return;
+        }
+        self.counts.add(&self.contest, vote, TallyMode::default());
+    }
+
+    /// The result for the votes counted so far
+    pub fn snapshot(&self) -> ContestResult {
+        let mut result = PluralityAtLarge::result_from_counts(&self.contest, self.counts.clone());
+        result.disqualified_choices = self.contest.choices().iter()
+            .filter(|c| self.contest.disqualified_choice_ids().contains(&c.id))
+            .cloned()
+            .collect();
+        result
+    }
+
+}
+
+/// An [IncrementalTally] restored from a checkpoint, see
+/// [Tally::checkpoint](crate::Tally::checkpoint).
+/// 
+/// It keeps track of how many votes have been processed, so that an
+/// interrupted import can skip the ones already counted and carry on.
+#[derive(Debug, Clone)]
+pub struct PartialTally {
+    tally: IncrementalTally,
+    processed: usize,
+}
+
+/// On-disk representation of a [PartialTally]: the running counts, not the
+/// votes themselves
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    contest: ContestId,
+    processed: usize,
+    counts: VoteCounts,
+}
+
+impl PartialTally {
+
+    /// Start counting votes for `contest` from scratch
+    pub fn new(contest: &Contest) -> Self {
+        Self{
+            tally: IncrementalTally::new(contest),
+            processed: 0,
+        }
+    }
+
+    /// The [Contest] being counted
+    pub fn contest(&self) -> &Contest {
+        self.tally.contest()
+    }
+
+    /// Number of votes processed so far, including the ones counted before
+    /// the checkpoint. Votes that were ignored also count.
+    pub fn processed(&self) -> usize {
+        self.processed
+    }
+
+    /// Counts a single vote, see [IncrementalTally::feed]
+    pub fn feed(&mut self, vote: &FlatVote) {
+        self.tally.feed(vote);
+        self.processed += 1;
+    }
+
+    /// The result for the votes counted so far
+    pub fn snapshot(&self) -> ContestResult {
+        self.tally.snapshot()
+    }
+
+    /// Saves the running counts to `path` and returns the number of votes
+    /// processed so far
+    pub fn checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<usize, Error> {
+        let checkpoint = Checkpoint{
+            contest: self.contest().id(),
+            processed: self.processed,
+            counts: self.tally.counts.clone(),
+        };
+        let serialized = serde_json::to_string(&checkpoint)?;
+        fs::write_atomically(path, |file| Ok(file.write_all(serialized.as_bytes())?))?;
+        Ok(self.processed)
+    }
+
+    /// Loads the counts saved by [PartialTally::checkpoint] for `contest`.
+    /// 
+    /// Fails with [Error::ContestMismatch] if the checkpoint belongs to a
+    /// different contest.
+    pub fn restore<P: AsRef<Path>>(path: P, contest: &Contest) -> Result<Self, Error> {
+        fs::load_with(path.as_ref(), |contents| {
+            let checkpoint: Checkpoint = serde_json::from_str(contents)?;
+            if checkpoint.contest != contest.id() {
+                return Err(Error::ContestMismatch{
+                    expected: contest.id(),
+                    actual: checkpoint.contest,
+                });
+            }
+            Ok(Self{
+                tally: IncrementalTally{
+                    contest: contest.clone(),
+                    counts: checkpoint.counts,
+                },
+                processed: checkpoint.processed,
+            })
+        })
+    }
+
+}
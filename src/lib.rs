@@ -11,6 +11,10 @@
 //! The main purpose of this library is processing votes ([DecodedContestVote])
 //! for a [Contest] and generating accurate results.
 //! 
+//! Votes are counted with [PluralityAtLarge] unless a different
+//! [TallyMethod] is set with [ContestBuilder::tally_method] or given to
+//! [Tally::with_method].
+//! 
 //! ### Example: Counting votes
 //! ```no_run
 //! use votes::{ContestBuilder, ContestChoice, DecodedContestVote, DecodedVoteChoice};
@@ -57,11 +61,21 @@
 //! ## Random generation
 //! Generate random contest and vote data for testing purposes.
 //! 
-//! There are three methods for random data generation available for use:
+//! The following methods for random data generation are available for use:
 //! 
 //! - [gen_random_choices] generates random choices for a [Contest]
+//! - [gen_random_choices_from] generates random choices with names from a
+//!   given list
 //! - [gen_random_contest] generates a random [Contest] with a set of choices
+//! - [gen_random_contest_builder] prepares a random [Contest] that can be
+//!   customized further
 //! - [gen_random_votes] generates random votes for a given [Contest]
+//! - [gen_random_votes_with_sources] generates random votes coming from
+//!   several channels
+//! - [gen_random_votes_with_invalid_ratio] generates random votes with a fixed
+//!   share of invalid votes
+//! - [gen_random_votes_biased] generates random votes where some choices are
+//!   more popular than others
 //! 
 //! ### Example
 //! 
@@ -155,18 +169,64 @@
 //! [^1]: [Plurality Block Voting - Wikipedia](https://en.wikipedia.org/wiki/Plurality_block_voting)
 
 
+// Instrumentation with the `tracing` feature, used by the modules below
+#[macro_use]
+mod trace;
+
 // Where the vote counting takes place
 mod tally;
 pub use tally::*;
 
+// Pluggable counting algorithms
+mod method;
+pub use method::*;
+
+// Counting votes as they arrive
+mod incremental;
+pub use incremental::*;
+
 // All our data structures should be available to the end-user
 mod contest;
 pub use contest::*;
 
+// Single transferable vote tallying
+mod stv;
+pub use stv::*;
+
+// D'Hondt proportional representation
+mod dhondt;
+pub use dhondt::*;
+
+// Two-round runoff tallying
+mod runoff;
+pub use runoff::*;
+
+// Exhaustive ballots, eliminating the last choice round after round
+mod exhaustive;
+pub use exhaustive::*;
+
+// Elections grouping several contests together
+mod election;
+pub use election::*;
+
 // The `gen` module exposes generation functions to create random
 mod gen;
 pub use gen::*;
 
+// Naming of the files written by the library
+mod naming;
+pub use naming::*;
+
+// JSON Schemas of the file formats
+#[cfg(feature = "schemars")]
+pub mod schema;
+
 // Errors produced by the library
 mod error;
-pub use error::Error;
\ No newline at end of file
+pub use error::{Error, ValidationError};
+
+// Helpers for reading and writing CSV files
+mod csv;
+
+// Helpers for writing files safely
+mod fs;
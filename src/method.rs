@@ -0,0 +1,232 @@
+// method.rs
+
+use std::fmt;
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::tally::VoteCounts;
+use crate::{Contest, ContestChoice, ContestChoiceResult, ContestResult, FlatVote, TallyMode, WRITE_IN_ID};
+
+/// A counting algorithm that turns the votes for a [Contest] into a [ContestResult].
+///
+/// [Tally](crate::Tally) counts with the method embedded in the contest by
+/// [ContestBuilder::tally_method](crate::ContestBuilder::tally_method), and
+/// with [PluralityAtLarge] if there is none. Any other method can be given to
+/// [Tally::with_method](crate::Tally::with_method).
+pub trait TallyMethod {
+
+    /// Identifies the method, stored as the tally type of the [Contest]s that
+    /// use it (see [ContestBuilder::tally_method](crate::ContestBuilder::tally_method))
+    fn name(&self) -> &str;
+
+    /// Counts `votes` for `contest`
+    fn tally(&self, contest: &Contest, votes: &[FlatVote]) -> ContestResult;
+
+    /// Same as [TallyMethod::tally], counting provisional votes or not
+    /// depending on `mode`.
+    /// 
+    /// By default provisional votes are left out before calling
+    /// [TallyMethod::tally], unless `mode` includes them.
+    fn tally_with_mode(&self, contest: &Contest, votes: &[FlatVote], mode: TallyMode) -> ContestResult {
+        match mode {
+            TallyMode::IncludeProvisional => self.tally(contest, votes),
+            TallyMode::ExcludeProvisional => {
+                let votes: Vec<FlatVote> = votes.iter()
+                    .filter(|v| !v.is_provisional())
+                    .cloned()
+                    .collect();
+                self.tally(contest, &votes)
+            },
+        }
+    }
+
+}
+
+/// A [TallyMethod] embedded in a [Contest], shared by all its copies
+#[derive(Clone)]
+pub(crate) struct EmbeddedMethod(pub(crate) Arc<dyn TallyMethod + Send + Sync>);
+
+impl fmt::Debug for EmbeddedMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EmbeddedMethod({})", self.0.name())
+    }
+}
+
+/// Plurality-at-large: every selected choice gets the votes assigned to it,
+/// and the most voted choices win.
+/// 
+/// Provisional votes are not counted, see [Tally::result_with_mode](crate::Tally::result_with_mode).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PluralityAtLarge;
+
+impl TallyMethod for PluralityAtLarge {
+
+    fn name(&self) -> &str {
+        "plurality-at-large"
+    }
+
+    fn tally(&self, contest: &Contest, votes: &[FlatVote]) -> ContestResult {
+        self.tally_with_mode(contest, votes, TallyMode::default())
+    }
+
+    fn tally_with_mode(&self, contest: &Contest, votes: &[FlatVote], mode: TallyMode) -> ContestResult {
+        Self::result_from_counts(contest, VoteCounts::from_votes(contest, votes, mode))
+    }
+
+}
+
+impl PluralityAtLarge {
+
+    /// Builds the [ContestResult] out of the per-choice vote counts
+    pub(crate) fn result_from_counts(contest: &Contest, counts: VoteCounts) -> ContestResult {
+
+        // Calculate winners
+        let mut sorted_results: Vec<(i64, u64)> = counts.counts.into_iter().collect();
+        sorted_results.sort_by_key(|(choice_id, votes)| (std::cmp::Reverse(*votes), *choice_id));
+
+        // Write-ins are reported but never ranked among the winners
+        let ranked: Vec<(i64, u64)> = sorted_results.iter()
+            .filter(|(choice_id, _)| *choice_id != WRITE_IN_ID)
+            .copied()
+            .collect();
+
+        // Calculate positions
+        let positions = Self::calc_positions(&ranked, contest.num_winners());
+
+        // Accumulate results for each choice as per exercise requirements
+        let write_in = ContestChoice::write_in();
+        let results = sorted_results.iter().map(|(choice_id, vote_count)| {
+            let choice = contest.choices().iter()
+                .chain(std::iter::once(&write_in))
+                .find(|c| c.id == *choice_id)
+                .expect("Got a vote for a choice that's not part of the contest");
+            let pos = positions.iter()
+                .find(|p| p.0 == *choice_id)
+                .map(|p| p.1)
+                .unwrap_or(0);
+            ContestChoiceResult{
+                contest_choice: choice.clone(),
+                total_count: *vote_count,
+                winner_position: pos as u64,
+            }
+        }).collect();
+
+        // Fill in the winners metadata
+        let cutoff = (contest.num_winners() as usize).min(ranked.len());
+        let winners = ranked[..cutoff].to_vec();
+        let winners = winners.into_iter().map(|(choice_id, _)| {
+            contest.choice_by_id(choice_id)
+                .expect("Failed to find winner choice")
+                .clone()
+        }).collect();
+
+        let total_valid_votes = counts.valid;
+        let quorum_met = contest.quorum().map(|q| total_valid_votes as u64 >= q);
+
+        // Difference between the top two choices
+        let margin_of_victory = match ranked.as_slice() {
+            [(_, first), (_, second), ..] => first - second,
+            _ => 0,
+        };
+        let margin_percentage = match total_valid_votes {
+            0 => 0.0,
+            total => margin_of_victory as f64 / total as f64 * 100.0,
+        };
+
+        let mut result = ContestResult{
+           contest: contest.clone(),
+           total_valid_votes,
+           total_invalid_votes: counts.invalid,
+           provisional_votes: counts.provisional,
+           results,
+           winners, 
+           quorum_met,
+           margin_of_victory,
+           margin_percentage,
+           calculated_at: Utc::now(),
+           exhausted_ballots: 0,
+           turnout: None,
+           entropy: 0.0,
+           disqualified_choices: vec![],
+           invalid_breakdown: counts.invalid_breakdown,
+           below_threshold_count: 0,
+        };
+        result.turnout = contest.eligible_voters()
+            .and_then(|voters| result.participation_rate(voters));
+        result.entropy = Self::entropy(&sorted_results);
+        result
+
+    }
+
+    /// Shannon entropy in bits of the share of votes of every choice, over
+    /// all the selections counted so that the shares add up to one
+    fn entropy(counts: &[(i64, u64)]) -> f64 {
+        let total: u64 = counts.iter().map(|(_, votes)| votes).sum();
+        counts.iter()
+            .filter(|(_, votes)| *votes > 0)
+            .map(|(_, votes)| {
+                let p = *votes as f64 / total as f64;
+                -p * p.log2()
+            })
+            .sum()
+    }
+
+    /// Calculate positions taking into account potential ties
+    /// Receives a sorted array of participants with their votes and
+    /// returns an array of participants with their positions
+    fn calc_positions(sorted_votes: &[(i64, u64)], num_winners: i64) -> Vec<(i64, i64)> {
+    
+        if sorted_votes.is_empty(){
+            return vec![];
+        }
+
+        let mut current_position = 1;
+        let mut current_votes = sorted_votes[0].1;
+
+        let mut positions = vec![(sorted_votes[0].0, current_position)];
+
+        for &vote in &sorted_votes[1..] {
+            if positions.len() >= num_winners as usize {
+                positions.push((vote.0, 0));
+            } else {
+                if vote.1 != current_votes {
+                    current_position += 1;
+                    current_votes = vote.1;
+                }
+                positions.push((vote.0, current_position));    
+            }
+        }
+
+        positions
+
+    }
+
+}
+
+
+#[test]
+fn test_calc_positions() {
+
+    let votes = vec![
+        (100, 10),
+        (200, 6),
+        (300, 6),
+        (400, 4),
+        (500, 1),
+        (600, 1),
+    ];
+
+    let positions = PluralityAtLarge::calc_positions(&votes, 5);
+
+    assert_eq!(1, positions[0].1);
+    assert_eq!(2, positions[1].1);
+    assert_eq!(2, positions[2].1);
+    assert_eq!(3, positions[3].1);
+    assert_eq!(4, positions[4].1);
+    // Last one should be zero because there's
+    // only 5 winners in this contest
+    assert_eq!(0, positions[5].1);
+
+}
+
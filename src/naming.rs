@@ -0,0 +1,41 @@
+// naming.rs
+
+use chrono::Utc;
+
+/// Decides the file names used by the `save_to_file_with` methods.
+///
+/// `kind` tells what is being saved (e.g. `"votes"`), and `id` is the ID of
+/// the contest or election it belongs to.
+pub trait FileNamingStrategy {
+    fn file_name(&self, kind: &str, id: i64) -> String;
+}
+
+/// `{kind}-{id}.json`, as used by all `save_to_file` methods
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DefaultNaming;
+
+impl FileNamingStrategy for DefaultNaming {
+    fn file_name(&self, kind: &str, id: i64) -> String {
+        format!("{}-{}.json", kind, id)
+    }
+}
+
+/// `{kind}-{id}-{timestamp}.json`, with the current UTC time down to the
+/// millisecond so that repeated saves don't overwrite each other
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimestampedNaming;
+
+impl FileNamingStrategy for TimestampedNaming {
+    fn file_name(&self, kind: &str, id: i64) -> String {
+        format!("{}-{}-{}.json", kind, id, Utc::now().format("%Y%m%dT%H%M%S%.3fZ"))
+    }
+}
+
+/// Names every file by calling a function with the ID
+pub struct CustomNaming(pub Box<dyn Fn(i64) -> String>);
+
+impl FileNamingStrategy for CustomNaming {
+    fn file_name(&self, _kind: &str, id: i64) -> String {
+        (self.0)(id)
+    }
+}
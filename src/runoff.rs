@@ -0,0 +1,110 @@
+// runoff.rs
+
+use serde::Serialize;
+
+use crate::{ContestChoice, ContestResult, Tally};
+
+/// Two-round runoff for single-winner contests.
+///
+/// If no choice gets more than half of the valid votes in the first round,
+/// the top two choices (the finalists) face each other in a second round.
+/// Ties for a spot among the finalists go to the choice with the lowest ID,
+/// as in [ContestResult::results].
+/// 
+/// The second round is counted from its own [Tally], usually for a contest
+/// with only the finalists on the ballot. Second round votes for any other
+/// choice are left out, see [Tally::result_excluding].
+#[derive(Debug, PartialEq)]
+pub struct RunoffTally {
+    round_one: Tally,
+    round_two: Tally,
+}
+
+/// The result of a [RunoffTally]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunoffResult {
+    /// The first round result
+    pub round_one: ContestResult,
+    /// The two choices advancing to the second round, empty if there is none.
+    /// Ties go to the choice with the lowest ID.
+    pub finalists: Vec<ContestChoice>,
+    /// The second round result, `None` if a choice won outright in the first
+    /// round. Only finalists can win it.
+    pub round_two: Option<ContestResult>,
+}
+
+impl RunoffResult {
+
+    /// The overall winner, taken from the last round that was held
+    pub fn winner(&self) -> Option<&ContestChoice> {
+        self.round_two.as_ref().unwrap_or(&self.round_one).winners.first()
+    }
+
+}
+
+impl RunoffTally {
+
+    pub fn new(round_one: Tally, round_two: Tally) -> Self {
+        Self{ round_one, round_two }
+    }
+
+    /// The first round [Tally]
+    pub fn round_one(&self) -> &Tally {
+        &self.round_one
+    }
+
+    /// The second round [Tally]
+    pub fn round_two(&self) -> &Tally {
+        &self.round_two
+    }
+
+    /// Whether no choice got more than half of the valid votes in the first
+    /// round. Never true without valid votes, as there are no finalists.
+    pub fn needed_round_two(&self) -> bool {
+        Self::needs_round_two(&self.round_one.result())
+    }
+
+    /// Count the first round and, if needed, the second one
+    pub fn result(&self) -> RunoffResult {
+
+        let round_one = self.round_one.result();
+        if !Self::needs_round_two(&round_one) {
+            return RunoffResult{
+                round_one,
+                finalists: vec![],
+                round_two: None,
+            };
+        }
+
+        let finalists: Vec<ContestChoice> = round_one.results.iter()
+            .filter(|r| !r.contest_choice.is_write_in())
+            .take(2)
+            .map(|r| r.contest_choice.clone())
+            .collect();
+        let eliminated: Vec<i64> = self.round_two.contest().choices().iter()
+            .filter(|c| !finalists.iter().any(|f| f.id == c.id))
+            .map(|c| c.id)
+            .collect();
+
+        RunoffResult{
+            round_one,
+            finalists,
+            round_two: Some(self.round_two.result_excluding(&eliminated)),
+        }
+
+    }
+
+    /// Whether the most voted choice got no more than half of the valid
+    /// votes, if there were any
+    fn needs_round_two(round_one: &ContestResult) -> bool {
+        if round_one.total_valid_votes <= 0 {
+            return false;
+        }
+        let top = round_one.results.iter()
+            .find(|r| !r.contest_choice.is_write_in())
+            .map(|r| r.total_count)
+            .unwrap_or(0);
+        top * 2 <= round_one.total_valid_votes as u64
+    }
+
+}
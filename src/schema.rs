@@ -0,0 +1,24 @@
+// schema.rs
+
+//! JSON Schemas for the files written by the library, for integrators
+//! validating them outside of Rust.
+
+use schemars::{schema::RootSchema, schema_for};
+
+use crate::{Contest, ContestResult, FlatVote};
+
+/// Schema of a [Contest] as written by [Contest::save_to_file]
+pub fn contest_schema() -> RootSchema {
+    schema_for!(Contest)
+}
+
+/// Schema of a single [FlatVote], i.e. every line of the files written by
+/// [Tally::save_to_file](crate::Tally::save_to_file) but the checksum
+pub fn flat_vote_schema() -> RootSchema {
+    schema_for!(FlatVote)
+}
+
+/// Schema of a [ContestResult] as written by [ContestResult::save_to_file]
+pub fn contest_result_schema() -> RootSchema {
+    schema_for!(ContestResult)
+}
@@ -0,0 +1,194 @@
+// stv.rs
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::{ContestChoice, FlatVote, Tally};
+
+/// Single transferable vote (STV) tallying for multi-winner contests.
+///
+/// Ballots rank choices in order of preference, using
+/// [DecodedVoteChoice::selected](crate::DecodedVoteChoice::selected) as the
+/// rank (1 = first preference). Surplus votes of elected choices are
+/// transferred proportionally to the next preferences (Gregory method).
+#[derive(Debug, PartialEq)]
+pub struct StvTally {
+    tally: Tally,
+}
+
+/// The result of an [StvTally]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StvResult {
+    /// The Droop quota needed to be elected
+    pub quota: u64,
+    /// Every counting round, in order
+    pub rounds: Vec<StvRound>,
+    /// The elected choices, in order of election
+    pub winners: Vec<ContestChoice>,
+    /// Ballots left without any choice in the running before all seats were filled
+    pub exhausted_ballots: u64,
+}
+
+/// A single counting round of an [StvTally]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StvRound {
+    /// Vote totals for every choice still in the running at the start of the round
+    pub totals: BTreeMap<i64, f64>,
+    /// Choices elected in this round
+    pub elected: Vec<i64>,
+    /// Choice eliminated in this round, if any
+    pub eliminated: Option<i64>,
+}
+
+/// Allows for rounding errors after fractional transfers when checking the quota
+const QUOTA_TOLERANCE: f64 = 1e-9;
+
+/// A ballot's preferences along with its current transfer value
+struct Ballot {
+    preferences: Vec<i64>,
+    weight: f64,
+    exhausted: bool,
+}
+
+impl Ballot {
+
+    /// The most preferred choice still in the running
+    fn current(&self, hopeful: &BTreeSet<i64>) -> Option<i64> {
+        self.preferences.iter().find(|id| hopeful.contains(id)).copied()
+    }
+
+}
+
+impl StvTally {
+
+    pub fn new(tally: Tally) -> Self {
+        Self{ tally }
+    }
+
+    /// The underlying [Tally]
+    pub fn tally(&self) -> &Tally {
+        &self.tally
+    }
+
+    /// The Droop quota: `floor(valid_votes / (num_winners + 1)) + 1`
+    pub fn quota(&self) -> u64 {
+        let valid_votes = self.tally.votes().iter().filter(|v| Self::is_counted(v)).count() as u64;
+        valid_votes / (self.tally.contest().num_winners().max(0) as u64 + 1) + 1
+    }
+
+    /// Count votes round by round until all seats are filled
+    pub fn result(&self) -> StvResult {
+
+        let contest = self.tally.contest();
+        let seats = contest.num_winners().max(0) as usize;
+        let quota = self.quota();
+
+        let mut ballots: Vec<Ballot> = self.tally.votes().iter()
+            .filter(|v| Self::is_counted(v))
+            .map(Self::ballot)
+            .collect();
+        let mut hopeful: BTreeSet<i64> = contest.choices().iter().map(|c| c.id).collect();
+        let mut elected: Vec<i64> = vec![];
+        let mut rounds = vec![];
+        let mut exhausted_ballots = 0;
+
+        while elected.len() < seats && !hopeful.is_empty() {
+
+            // Current totals for every choice still in the running
+            let mut totals: BTreeMap<i64, f64> = hopeful.iter().map(|id| (*id, 0.0)).collect();
+            for ballot in ballots.iter_mut() {
+                match ballot.current(&hopeful) {
+                    Some(id) => *totals.entry(id).or_default() += ballot.weight,
+                    None if !ballot.exhausted => {
+                        ballot.exhausted = true;
+                        exhausted_ballots += 1;
+                    },
+                    None => (),
+                }
+            }
+
+            // Not enough choices left to compete for the remaining seats
+            if hopeful.len() <= seats - elected.len() {
+                let mut remaining: Vec<(i64, f64)> = totals.iter().map(|(id, t)| (*id, *t)).collect();
+                remaining.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+                let round_elected: Vec<i64> = remaining.into_iter().map(|(id, _)| id).collect();
+                elected.extend(&round_elected);
+                rounds.push(StvRound{ totals, elected: round_elected, eliminated: None });
+                break;
+            }
+
+            // Elect every choice that meets the quota, highest total first
+            let mut round_elected: Vec<(i64, f64)> = totals.iter()
+                .filter(|(_, total)| **total + QUOTA_TOLERANCE >= quota as f64)
+                .map(|(id, total)| (*id, *total))
+                .collect();
+            round_elected.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+            round_elected.truncate(seats - elected.len());
+
+            if !round_elected.is_empty() {
+                // Transfer surplus votes proportionally
+                for (id, total) in &round_elected {
+                    let transfer_value = (total - quota as f64) / total;
+                    for ballot in ballots.iter_mut().filter(|b| b.current(&hopeful) == Some(*id)) {
+                        ballot.weight *= transfer_value;
+                    }
+                }
+                for (id, _) in &round_elected {
+                    hopeful.remove(id);
+                    elected.push(*id);
+                }
+                rounds.push(StvRound{
+                    totals,
+                    elected: round_elected.into_iter().map(|(id, _)| id).collect(),
+                    eliminated: None,
+                });
+                continue;
+            }
+
+            // Nobody met the quota: eliminate the last-placed choice
+            let (last, _) = totals.iter()
+                .min_by(|a, b| a.1.total_cmp(b.1).then(b.0.cmp(a.0)))
+                .map(|(id, total)| (*id, *total))
+                .expect("There should be at least one choice in the running");
+            hopeful.remove(&last);
+            rounds.push(StvRound{ totals, elected: vec![], eliminated: Some(last) });
+
+        }
+
+        let winners = elected.iter()
+            .filter_map(|id| contest.choice_by_id(*id))
+            .cloned()
+            .collect();
+
+        StvResult{
+            quota,
+            rounds,
+            winners,
+            exhausted_ballots,
+        }
+
+    }
+
+    /// Whether a vote takes part in the count
+    fn is_counted(vote: &FlatVote) -> bool {
+        !vote.is_invalid() && !vote.is_provisional()
+    }
+
+    /// Turns a vote into a full-weight ballot ordered by preference
+    fn ballot(vote: &FlatVote) -> Ballot {
+        let mut ranked: Vec<(u64, i64)> = vote.choices().iter()
+            .filter(|c| c.selected > 0)
+            .map(|c| (c.selected, c.contest_choice.id))
+            .collect();
+        ranked.sort();
+        let mut preferences: Vec<i64> = vec![];
+        for (_, id) in ranked {
+            if !preferences.contains(&id) {
+                preferences.push(id);
+            }
+        }
+        Ballot{ preferences, weight: 1.0, exhausted: false }
+    }
+
+}
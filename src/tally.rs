@@ -1,16 +1,26 @@
 // tally.rs
 
-use std::collections::HashMap;
-use std::{fs::File, path::Path};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::path::{Path, PathBuf};
 use std::io::{BufRead, BufReader, Write};
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+#[cfg(feature = "compression")]
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 
-use crate::{Contest, ContestChoice, DecodedContestVote, Error};
-use crate::DecodedVoteChoice;
+use crate::{Contest, ContestChoice, ContestId, ContestPhase, DecodedContestVote, DefaultNaming, Error, FileNamingStrategy, PluralityAtLarge, TallyMethod};
+use crate::{DecodedVoteChoice, IncrementalTally, InvalidReason, PartialTally, VoteValidity, SCHEMA_VERSION};
+use crate::{csv, fs};
 
 /// The aggregated result of a [Tally]
-#[derive(Debug, Serialize)]
+/// 
+/// Two results are equal if everything but [ContestResult::calculated_at] matches.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ContestResult {
     /// The [Contest] to which these results belong
     pub contest: Contest,
@@ -18,29 +28,542 @@ pub struct ContestResult {
     pub total_valid_votes: i64,
     /// Total invalid votes
     pub total_invalid_votes: i64,
-    /// The results for every choice
+    /// The results for every choice, by descending vote count (ties by ID)
     pub results: Vec<ContestChoiceResult>,
     /// The winners for the contest
     pub winners: Vec<ContestChoice>,
+    /// Total provisional votes, whether they were counted or not
+    pub provisional_votes: u64,
+    /// Whether the contest quorum was met (`None` if the contest has no quorum)
+    pub quorum_met: Option<bool>,
+    /// Votes separating the first and second-placed choices (zero on a tie)
+    pub margin_of_victory: u64,
+    /// [ContestResult::margin_of_victory] as a percentage of the total valid votes
+    pub margin_percentage: f64,
+    /// When the votes were counted
+    #[serde(default)]
+    pub calculated_at: DateTime<Utc>,
+    /// Ballots whose choices were all eliminated in an earlier round of
+    /// [Tally::multi_round_result], zero for single-round counts
+    #[serde(default)]
+    pub exhausted_ballots: u64,
+    /// [ContestResult::participation_rate] for the eligible voters of the
+    /// contest (`None` if unknown)
+    #[serde(default)]
+    pub turnout: Option<f64>,
+    /// Shannon entropy in bits of the vote shares of the choices, zero when a
+    /// single choice gets every vote. Shares are taken over all the counted
+    /// selections, which matches the valid votes when ballots have one choice.
+    #[serde(default)]
+    pub entropy: f64,
+    /// Choices whose votes were left out of the count, see
+    /// [Contest::disqualify_choice] and [Tally::result_excluding]
+    #[serde(default)]
+    pub disqualified_choices: Vec<ContestChoice>,
+    /// Why the invalid votes were invalid
+    #[serde(default)]
+    pub invalid_breakdown: InvalidVoteBreakdown,
+    /// Choices that ranked among the winners but were left out for falling
+    /// short of a vote threshold, see [Tally::result_with_threshold]
+    #[serde(default)]
+    pub below_threshold_count: u64,
+}
+
+impl PartialEq for ContestResult {
+    fn eq(&self, other: &Self) -> bool {
+        self.contest == other.contest
+            && self.total_valid_votes == other.total_valid_votes
+            && self.total_invalid_votes == other.total_invalid_votes
+            && self.results == other.results
+            && self.winners == other.winners
+            && self.provisional_votes == other.provisional_votes
+            && self.quorum_met == other.quorum_met
+            && self.margin_of_victory == other.margin_of_victory
+            && self.margin_percentage == other.margin_percentage
+            && self.turnout == other.turnout
+            && self.exhausted_ballots == other.exhausted_ballots
+            && self.entropy == other.entropy
+            && self.disqualified_choices == other.disqualified_choices
+            && self.invalid_breakdown == other.invalid_breakdown
+            && self.below_threshold_count == other.below_threshold_count
+    }
 }
 
 impl ContestResult {
 
+    /// The winners for the contest, or none if the quorum was not met
+    pub fn effective_winners(&self) -> &[ContestChoice] {
+        match self.quorum_met {
+            Some(false) => &[],
+            _ => &self.winners,
+        }
+    }
+
+    /// Choices in [ContestResult::results] that did not win, by descending
+    /// vote count. Write-ins are left out.
+    pub fn losers(&self) -> Vec<&ContestChoice> {
+        self.results.iter()
+            .filter(|r| r.winner_position == 0 && !r.contest_choice.is_write_in())
+            .map(|r| &r.contest_choice)
+            .collect()
+    }
+
+    /// The most voted choice that did not win, next in line for a seat
+    pub fn runner_up(&self) -> Option<&ContestChoice> {
+        self.losers().into_iter().next()
+    }
+
+    /// Time elapsed since the result was calculated, e.g. to tell whether it
+    /// is stale. Results saved before this was recorded are as old as the
+    /// Unix epoch.
+    pub fn age(&self) -> chrono::Duration {
+        Utc::now() - self.calculated_at
+    }
+
+    /// The `n` most voted results, regardless of the number of winners
+    pub fn top_n(&self, n: usize) -> Vec<&ContestChoiceResult> {
+        let mut results = self.results_by_count();
+        results.truncate(n);
+        results
+    }
+
+    /// The result for the choice with ID `choice_id`, if it received any votes
+    pub fn choice_result_for(&self, choice_id: i64) -> Option<&ContestChoiceResult> {
+        self.results.iter().find(|r| r.contest_choice.id == choice_id)
+    }
+
+    /// The results sorted by descending vote count
+    pub fn results_by_count(&self) -> Vec<&ContestChoiceResult> {
+        let mut results: Vec<&ContestChoiceResult> = self.results.iter().collect();
+        results.sort_by_key(|r| std::cmp::Reverse(r.total_count));
+        results
+    }
+
+    /// The results sorted alphabetically by choice text
+    pub fn results_by_name(&self) -> Vec<&ContestChoiceResult> {
+        let mut results: Vec<&ContestChoiceResult> = self.results.iter().collect();
+        results.sort_by(|a, b| a.contest_choice.text.cmp(&b.contest_choice.text));
+        results
+    }
+
+    /// The results sorted by ascending choice ID
+    pub fn results_by_id(&self) -> Vec<&ContestChoiceResult> {
+        let mut results: Vec<&ContestChoiceResult> = self.results.iter().collect();
+        results.sort_by_key(|r| r.contest_choice.id);
+        results
+    }
+
+    /// The results grouped by the category of their choice, by descending
+    /// vote count within each group
+    pub fn results_by_category(&self) -> HashMap<Option<String>, Vec<&ContestChoiceResult>> {
+        let mut groups: HashMap<Option<String>, Vec<&ContestChoiceResult>> = HashMap::new();
+        for result in &self.results {
+            groups.entry(result.contest_choice.category.clone()).or_default().push(result);
+        }
+        groups
+    }
+
+    /// The winners grouped by their position, so that ties end up together
+    pub fn winners_by_position(&self) -> BTreeMap<u64, Vec<&ContestChoice>> {
+        let mut positions: BTreeMap<u64, Vec<&ContestChoice>> = BTreeMap::new();
+        for winner in &self.winners {
+            if let Some(r) = self.choice_result_for(winner.id) {
+                positions.entry(r.winner_position).or_default().push(winner);
+            }
+        }
+        positions
+    }
+
+    /// A compact and deterministic summary of this result, to compare
+    /// independent counts of the same contest
+    pub fn audit_summary(&self) -> AuditSummary {
+        let mut winners: Vec<(u64, i64)> = self.winners.iter()
+            .map(|w| (self.choice_result_for(w.id).map(|r| r.winner_position).unwrap_or_default(), w.id))
+            .collect();
+        winners.sort_unstable();
+        AuditSummary{
+            contest_id: self.contest.id(),
+            total_valid: self.total_valid_votes,
+            total_invalid: self.total_invalid_votes,
+            choice_counts: self.results.iter()
+                .map(|r| (r.contest_choice.id, r.total_count))
+                .collect(),
+            winners: winners.into_iter().map(|(_, id)| id).collect(),
+        }
+    }
+
+    /// A copy of the counts without anything identifying the choices, e.g.
+    /// to publish intermediate results without revealing who is who.
+    /// 
+    /// Every choice gets a pseudonym made of an ID hashed from the contest,
+    /// the choice ID and the names of every choice of the contest, and a
+    /// `Candidate X` label assigned in order of those IDs. The same contest
+    /// always gets the same pseudonyms, which can't be turned back into
+    /// choices without the original contest: knowing the choice IDs alone
+    /// is not enough.
+    pub fn anonymized(&self) -> AnonymizedResult {
+        let contest_id = self.contest.id();
+        let key = pseudonym_key(&self.contest);
+        let mut pseudonyms: Vec<(i64, i64)> = self.results.iter()
+            .map(|r| r.contest_choice.id)
+            .chain(self.winners.iter().map(|w| w.id))
+            .collect::<BTreeSet<i64>>()
+            .into_iter()
+            .map(|id| (id, pseudonym_id(&key, id)))
+            .collect();
+        pseudonyms.sort_unstable_by_key(|(_, pseudonym)| *pseudonym);
+        let anonymize = |choice: &ContestChoice| {
+            let index = pseudonyms.iter().position(|(id, _)| *id == choice.id)
+                .expect("Every choice has a pseudonym");
+            ContestChoice::new(pseudonyms[index].1, &format!("Candidate {}", candidate_label(index)))
+        };
+
+        let mut results: Vec<ContestChoiceResult> = self.results.iter()
+            .map(|r| ContestChoiceResult{
+                contest_choice: anonymize(&r.contest_choice),
+                ..r.clone()
+            })
+            .collect();
+        // Ties must not give away the order of the original IDs
+        results.sort_by_key(|r| (std::cmp::Reverse(r.total_count), r.contest_choice.id));
+        let mut winners: Vec<(u64, ContestChoice)> = self.winners.iter()
+            .map(|w| {
+                let votes = self.choice_result_for(w.id).map(|r| r.total_count).unwrap_or(0);
+                (votes, anonymize(w))
+            })
+            .collect();
+        let mut start = 0;
+        while start < winners.len() {
+            let tied = winners[start..].iter()
+                .take_while(|(votes, _)| *votes == winners[start].0)
+                .count();
+            winners[start..start + tied].sort_by_key(|(_, choice)| choice.id);
+            start += tied;
+        }
+
+        AnonymizedResult{
+            contest_id,
+            total_valid_votes: self.total_valid_votes,
+            total_invalid_votes: self.total_invalid_votes,
+            results,
+            winners: winners.into_iter().map(|(_, choice)| choice).collect(),
+        }
+    }
+
+    /// Total write-in votes, or `None` if the contest doesn't allow write-ins
+    pub fn write_in_votes(&self) -> Option<u64> {
+        if !self.contest.allows_write_ins() {
+            return None;
+        }
+        let count = self.results.iter()
+            .find(|r| r.contest_choice.is_write_in())
+            .map(|r| r.total_count)
+            .unwrap_or(0);
+        Some(count)
+    }
+
+    /// Total number of individual selections across all choices (the sum of
+    /// every [ContestChoiceResult::total_count])
+    pub fn total_expressed_votes(&self) -> u64 {
+        self.results.iter().map(|r| r.total_count).sum()
+    }
+
+    /// Average number of selections per valid vote, zero if there are none
+    pub fn average_choices_per_ballot(&self) -> f64 {
+        match self.total_valid_votes {
+            0 => 0.0,
+            total => self.total_expressed_votes() as f64 / total as f64,
+        }
+    }
+
+    /// Whether the winner leads the runner-up by at least
+    /// `margin_threshold_pct` percentage points. Ties are never decisive.
+    pub fn is_decisive(&self, margin_threshold_pct: f64) -> bool {
+        self.margin_of_victory > 0 && self.margin_percentage >= margin_threshold_pct
+    }
+
+    /// How settled the result is, from `0.0` (a tie) to `1.0` (every valid
+    /// vote separates the top two choices). Based on the margin of victory
+    /// relative to the total valid votes.
+    pub fn confidence_level(&self) -> f64 {
+        (self.margin_percentage / 100.0).clamp(0.0, 1.0)
+    }
+
+    /// The current leader of a partial count, if their lead can no longer be
+    /// overturned by the votes still to be counted.
+    ///
+    /// `votes_counted_pct` is the share of votes counted so far, from `0.0`
+    /// to `1.0`. The leader is projected when the margin of victory exceeds
+    /// the votes still to be counted, estimated as
+    /// `(1.0 - votes_counted_pct) * total_valid_votes`. Returns `None` while
+    /// the race is too close to call.
+    pub fn projected_winner(&self, votes_counted_pct: f64) -> Option<&ContestChoice> {
+        let remaining = (1.0 - votes_counted_pct.clamp(0.0, 1.0)) * self.total_valid_votes as f64;
+        match self.margin_of_victory as f64 > remaining {
+            true => self.winners.first(),
+            false => None,
+        }
+    }
+
+    /// Distribution statistics over the vote counts of every choice in
+    /// [ContestResult::results]
+    pub fn statistics(&self) -> VoteStatistics {
+        let counts: Vec<u64> = self.results.iter().map(|r| r.total_count).collect();
+        VoteStatistics::from_counts(&counts)
+    }
+
+    /// [ContestResult::entropy] relative to its maximum for the number of
+    /// choices, from `0.0` (a single choice got every vote) to `1.0` (all
+    /// choices got the same votes). Zero for contests with fewer than two choices.
+    pub fn normalized_entropy(&self) -> f64 {
+        match self.contest.num_choices() {
+            0 | 1 => 0.0,
+            n => (self.entropy / (n as f64).log2()).clamp(0.0, 1.0),
+        }
+    }
+
+    /// Share of valid votes that were exhausted, zero if there are none
+    pub fn exhaustion_rate(&self) -> f64 {
+        match self.total_valid_votes {
+            0 => 0.0,
+            total => self.exhausted_ballots as f64 / total as f64,
+        }
+    }
+
+    /// Share of `eligible_voters` who cast a ballot, valid or not.
+    /// Returns `None` if there are no eligible voters.
+    pub fn participation_rate(&self, eligible_voters: u64) -> Option<f64> {
+        let cast = self.total_valid_votes + self.total_invalid_votes;
+        (eligible_voters > 0).then(|| cast as f64 / eligible_voters as f64)
+    }
+
+    /// Same as [ContestResult::participation_rate], counting only valid ballots
+    pub fn valid_participation_rate(&self, eligible_voters: u64) -> Option<f64> {
+        (eligible_voters > 0).then(|| self.total_valid_votes as f64 / eligible_voters as f64)
+    }
+
+    /// Encodes the results as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(&self)?)
+    }
+
+    /// Decodes results from a JSON string
+    pub fn from_json(s: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(s)?)
+    }
+
     /// Saves the results to a JSON-encoded file and returns the filename
     pub fn save_to_file(&self) -> Result<String, Error> {
-        let fname = format!("results-{}.json", self.contest.id());
-        let mut file = File::create(&fname)?;
+        self.save_to_file_with(&DefaultNaming)
+    }
+
+    /// Same as [ContestResult::save_to_file], with the file named by `naming`
+    pub fn save_to_file_with(&self, naming: &dyn FileNamingStrategy) -> Result<String, Error> {
+        let fname = naming.file_name("results", self.contest.id().0);
         let serialized = serde_json::to_string_pretty(&self)?;
-        file.write_all(serialized.as_bytes())?;
-        file.flush()?;
+        fs::write_atomically(&fname, |file| Ok(file.write_all(serialized.as_bytes())?))?;
         Ok(fname)
     }
 
+    /// Loads results from a JSON file written by [ContestResult::save_to_file]
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        fs::load_json(path.as_ref())
+    }
+
+    /// Renders the results as a CSV table, one row per choice.
+    /// 
+    /// Rows are sorted by winner position, with non-winners last. The
+    /// percentage is relative to the total valid votes.
+    pub fn to_csv(&self) -> String {
+
+        let mut results: Vec<&ContestChoiceResult> = self.results.iter().collect();
+        results.sort_by_key(|r| (r.winner_position == 0, r.winner_position, std::cmp::Reverse(r.total_count)));
+
+        let mut out = String::from("choice_id,choice_text,total_count,percentage,winner_position\n");
+        for r in results {
+            let percentage = self.percentage_of_valid(r.total_count);
+            out.push_str(&format!("{},{},{},{:.2},{}\n",
+                r.contest_choice.id,
+                csv::escape_field(&r.contest_choice.text),
+                r.total_count,
+                percentage,
+                r.winner_position,
+            ));
+        }
+        out
+
+    }
+
+    /// Renders the results as a Markdown table with `Rank | Candidate | Votes | %`
+    /// columns, by descending vote count. Tied choices share the same rank.
+    pub fn to_markdown_table(&self) -> String {
+        let mut out = String::from("| Rank | Candidate | Votes | % |\n|---:|---|---:|---:|\n");
+        for (rank, r) in self.ranked_results() {
+            out.push_str(&format!("| {} | {} | {} | {:.2} |\n",
+                rank,
+                r.contest_choice.text.replace('|', "\\|"),
+                r.total_count,
+                self.percentage_of_valid(r.total_count),
+            ));
+        }
+        out
+    }
+
+    /// Renders the results as an HTML `<table>` with the same columns as
+    /// [ContestResult::to_markdown_table]. Rows for winners have the `winner` class.
+    pub fn to_html_table(&self) -> String {
+        let mut out = String::from("<table>\n  <thead>\n    <tr><th>Rank</th><th>Candidate</th><th>Votes</th><th>%</th></tr>\n  </thead>\n  <tbody>\n");
+        for (rank, r) in self.ranked_results() {
+            let class = match r.winner_position {
+                0 => "",
+                _ => " class=\"winner\"",
+            };
+            out.push_str(&format!("    <tr{}><td>{}</td><td>{}</td><td>{}</td><td>{:.2}</td></tr>\n",
+                class,
+                rank,
+                escape_html(&r.contest_choice.text),
+                r.total_count,
+                self.percentage_of_valid(r.total_count),
+            ));
+        }
+        out.push_str("  </tbody>\n</table>\n");
+        out
+    }
+
+    /// The results by descending vote count along with their rank, where
+    /// tied choices share the same rank (e.g. 1, 2, 2, 4)
+    fn ranked_results(&self) -> Vec<(usize, &ContestChoiceResult)> {
+        let mut ranked: Vec<(usize, &ContestChoiceResult)> = vec![];
+        for (i, r) in self.results_by_count().into_iter().enumerate() {
+            let rank = match ranked.last() {
+                Some((rank, prev)) if prev.total_count == r.total_count => *rank,
+                _ => i + 1,
+            };
+            ranked.push((rank, r));
+        }
+        ranked
+    }
+
+    /// `count` as a percentage of the total valid votes
+    fn percentage_of_valid(&self, count: u64) -> f64 {
+        match self.total_valid_votes {
+            0 => 0.0,
+            total => count as f64 / total as f64 * 100.0,
+        }
+    }
+
+    /// Saves the results as a CSV table to `path`. See [ContestResult::to_csv].
+    pub fn save_results_to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let csv = self.to_csv();
+        fs::write_atomically(path, |file| Ok(file.write_all(csv.as_bytes())?))
+    }
+
+}
+
+impl fmt::Display for ContestResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.contest)?;
+        writeln!(f, "Valid votes: {}", self.total_valid_votes)?;
+        writeln!(f, "Invalid votes: {}", self.total_invalid_votes)?;
+        write!(f, "Winners:")?;
+        for winner in &self.winners {
+            let (position, count) = self.choice_result_for(winner.id)
+                .map(|r| (r.winner_position, r.total_count))
+                .unwrap_or_default();
+            write!(f, "\n  {}. {} ({} votes)", position, winner, count)?;
+        }
+        Ok(())
+    }
+}
+
+
+/// The essentials of a [ContestResult], see [ContestResult::audit_summary]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditSummary {
+    pub contest_id: ContestId,
+    pub total_valid: i64,
+    pub total_invalid: i64,
+    /// Votes for every choice that got any, by choice ID
+    pub choice_counts: BTreeMap<i64, u64>,
+    /// Winner IDs by position, with ties by ID
+    pub winners: Vec<i64>,
+}
+
+impl AuditSummary {
+
+    /// SHA-256 hex digest of the compact JSON encoding of this summary.
+    /// Matching checksums mean matching summaries.
+    pub fn checksum(&self) -> String {
+        let json = serde_json::to_string(self).expect("Failed to encode audit summary");
+        format!("{:x}", Sha256::digest(json.as_bytes()))
+    }
+
+}
+
+/// A [ContestResult] with pseudonyms instead of the actual choices, see
+/// [ContestResult::anonymized]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnonymizedResult {
+    pub contest_id: ContestId,
+    pub total_valid_votes: i64,
+    pub total_invalid_votes: i64,
+    /// The results for every choice, by descending vote count (ties by pseudonym ID)
+    pub results: Vec<ContestChoiceResult>,
+    /// The winners, in the same order as in the original result except for
+    /// ties, which are sorted by pseudonym ID
+    pub winners: Vec<ContestChoice>,
+}
+
+/// Hash of the contest ID and the ID and name of every choice, which
+/// pseudonyms are derived from
+fn pseudonym_key(contest: &Contest) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(contest.id().0.to_le_bytes());
+    for choice in contest.choices() {
+        hasher.update(choice.id.to_le_bytes());
+        hasher.update((choice.text.len() as u64).to_le_bytes());
+        hasher.update(choice.text.as_bytes());
+    }
+    hasher.finalize().into()
+}
+
+/// Pseudonym ID of the choice `choice_id`, taken from a SHA-256 hash of the
+/// contest's [pseudonym_key] and the choice ID
+fn pseudonym_id(key: &[u8; 32], choice_id: i64) -> i64 {
+    let digest = Sha256::new()
+        .chain_update(key)
+        .chain_update(choice_id.to_le_bytes())
+        .finalize();
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    i64::from_le_bytes(bytes)
+}
+
+/// Spreadsheet-style label for the `index`-th pseudonym: A to Z, then AA, AB...
+fn candidate_label(index: usize) -> String {
+    let mut label = vec![];
+    let mut n = index + 1;
+    while n > 0 {
+        n -= 1;
+        label.push(b'A' + (n % 26) as u8);
+        n /= 26;
+    }
+    label.reverse();
+    String::from_utf8(label).expect("Labels are ASCII")
+}
+
+/// Escapes the characters with a special meaning in HTML
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
 
 
 /// Detailed tally results for a given [ContestChoice]
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ContestChoiceResult {
     /// The choice
     pub contest_choice: ContestChoice,
@@ -50,12 +573,122 @@ pub struct ContestChoiceResult {
     pub winner_position: u64,
 }
 
+/// Invalid votes of a [ContestResult] by reason, see [InvalidReason].
+/// 
+/// Flat votes don't keep the reason, so it is worked out again from the
+/// contest rules: invalid votes with a choice that is not part of the contest
+/// are counted apart, those with too few or too many choices are under or
+/// overvotes, and the rest are counted as `other`. This means explicitly
+/// invalidated votes, e.g. [FlatVote::blank], are only told apart when their
+/// choices meet the contest rules.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct InvalidVoteBreakdown {
+    /// Votes with fewer choices than the contest minimum
+    pub undervotes: u64,
+    /// Votes with more choices than the contest maximum
+    pub overvotes: u64,
+    /// Invalid votes whose choices meet the contest rules, so they must have
+    /// been invalidated explicitly
+    pub other: u64,
+    /// Votes with a choice that is not part of the contest
+    #[serde(default)]
+    pub not_in_contest: u64,
+}
+
+impl InvalidVoteBreakdown {
+
+    /// Counts an invalid `vote` under its reason
+    fn add(&mut self, contest: &Contest, vote: &FlatVote) {
+        let count = vote.choices.len() as i64;
+        if !Self::choices_in_contest(contest, vote) {
+            self.not_in_contest += 1;
+        } else if count < contest.min_choices() {
+            self.undervotes += 1;
+        } else if count > contest.max_choices() {
+            self.overvotes += 1;
+        } else {
+            self.other += 1;
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    fn merge(self, other: Self) -> Self {
+        Self{
+            undervotes: self.undervotes + other.undervotes,
+            overvotes: self.overvotes + other.overvotes,
+            other: self.other + other.other,
+            not_in_contest: self.not_in_contest + other.not_in_contest,
+        }
+    }
+
+    /// Whether every choice of `vote` is part of `contest`
+    fn choices_in_contest(contest: &Contest, vote: &FlatVote) -> bool {
+        vote.choices.iter().all(|c| contest.is_choice_valid(&c.contest_choice))
+    }
+
+}
+
+/// Statistics over the vote counts of the choices of a [ContestResult].
+/// All of them are zero if there are no results.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct VoteStatistics {
+    /// Average votes per choice
+    pub mean: f64,
+    /// Middle vote count, or the average of the two middle ones
+    pub median: f64,
+    /// Population standard deviation of the vote counts
+    pub std_dev: f64,
+    /// Gini coefficient, from `0.0` (all choices got the same votes) towards
+    /// `1.0` (a single choice got every vote)
+    pub gini: f64,
+}
+
+impl VoteStatistics {
+
+    fn from_counts(counts: &[u64]) -> Self {
+        let n = counts.len();
+        let total: u64 = counts.iter().sum();
+        if n == 0 {
+            return Self{ mean: 0.0, median: 0.0, std_dev: 0.0, gini: 0.0 };
+        }
+
+        let mut sorted = counts.to_vec();
+        sorted.sort_unstable();
+
+        let mean = total as f64 / n as f64;
+        let median = match n % 2 {
+            0 => (sorted[n / 2 - 1] + sorted[n / 2]) as f64 / 2.0,
+            _ => sorted[n / 2] as f64,
+        };
+        let variance = sorted.iter()
+            .map(|&c| (c as f64 - mean).powi(2))
+            .sum::<f64>() / n as f64;
+
+        // G = Σ (2i - n - 1) * xᵢ / (n * Σ xᵢ), with xᵢ ascending and i from 1
+        let gini = match total {
+            0 => 0.0,
+            _ => sorted.iter().enumerate()
+                .map(|(i, &c)| (2 * (i + 1) as i64 - n as i64 - 1) as f64 * c as f64)
+                .sum::<f64>() / (n as f64 * total as f64),
+        };
+
+        Self{ mean, median, std_dev: variance.sqrt(), gini }
+    }
+
+}
+
 /// Vote tallying for any [Contest].
 /// Includes the [Contest] object and the collection of submited votes as [FlatVote]s.
-#[derive(Debug, PartialEq)]
-pub struct Tally {
+/// 
+/// Votes are counted with the [TallyMethod] of the contest (see
+/// [Contest::tally_method]), unless a method `M` is given to [Tally::with_method].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tally<M: TallyMethod = PluralityAtLarge> {
     contest: Contest,
     votes: Vec<FlatVote>,
+    /// `None` to count with the method of the contest
+    method: Option<M>,
 }
 
 /// Homologous to [DecodedContestVote] but doesn't include the full
@@ -64,22 +697,590 @@ pub struct Tally {
 /// When working with large samples of vote data, having the [Contest] object
 /// included in each vote is redundant and leads to unnecessary memory and
 /// disk usage.
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct FlatVote {
     is_explicit_invalid: bool,
     choices: Vec<DecodedVoteChoice>,
-    contest: i64,
+    contest: ContestId,
+    #[serde(default)]
+    voter_id: Option<String>,
+    #[serde(default)]
+    is_provisional: bool,
+    #[serde(default)]
+    submitted_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    schema_version: u32,
+}
+
+/// Whether [Tally::result_with_mode] counts provisional votes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TallyMode {
+    /// Count provisional votes like any other vote
+    IncludeProvisional,
+    /// Leave provisional votes out of the count
+    #[default]
+    ExcludeProvisional,
+}
+
+impl FlatVote {
+
+    /// An explicitly invalid vote with no choices for `contest`, used to
+    /// represent abstentions
+    pub fn blank(contest: &Contest) -> Self {
+        Self{
+            is_explicit_invalid: true,
+            choices: vec![],
+            contest: contest.id(),
+            voter_id: None,
+            is_provisional: false,
+            submitted_at: None,
+            source: None,
+            schema_version: SCHEMA_VERSION,
+        }
+    }
+
+    /// Indicates that this vote is invalid
+    pub fn is_invalid(&self) -> bool {
+        self.is_explicit_invalid
+    }
+
+    /// Explicitly invalidates this vote
+    pub fn invalidate(&mut self) {
+        self.is_explicit_invalid = true;
+    }
+
+    /// The ID of the [Contest] this vote was emitted for, e.g. to route
+    /// votes for several contests to the right [Tally]
+    pub fn contest_id(&self) -> ContestId {
+        self.contest
+    }
+
+    /// The version of the serialization format this vote was written with,
+    /// see [SCHEMA_VERSION]
+    pub fn schema_version(&self) -> u32 {
+        self.schema_version
+    }
+
+    /// Upgrades a vote decoded from an older serialization format to
+    /// [SCHEMA_VERSION]
+    pub(crate) fn migrate(self) -> Result<Self, Error> {
+        match self.schema_version {
+            0 => Ok(Self::migrate_v0_to_v1(self)),
+            SCHEMA_VERSION => Ok(self),
+            version => Err(Error::UnsupportedSchemaVersion(version)),
+        }
+    }
+
+    /// Votes written before versioning have the same fields, with the ones
+    /// added later already filled in with their defaults
+    fn migrate_v0_to_v1(self) -> Self {
+        Self{
+            schema_version: 1,
+            ..self
+        }
+    }
+
+    /// Indicates that this is a provisional vote pending validation
+    pub fn is_provisional(&self) -> bool {
+        self.is_provisional
+    }
+
+    /// Marks this vote as provisional
+    pub fn mark_provisional(&mut self) {
+        self.is_provisional = true;
+    }
+
+    /// Marks this vote as no longer provisional, e.g. once validated
+    pub fn clear_provisional(&mut self) {
+        self.is_provisional = false;
+    }
+
+    /// Encodes this vote in a compact binary format
+    #[cfg(feature = "bincode")]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    /// Decodes a vote previously encoded with [FlatVote::to_bytes]
+    /// 
+    /// The binary format isn't self-describing, so fields missing from older
+    /// encodings can't be defaulted and these votes are not migrated: votes
+    /// written before [SCHEMA_VERSION] fail with [Error::Bincode]. Use JSON
+    /// to keep votes across versions of this library.
+    #[cfg(feature = "bincode")]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        bincode::deserialize::<Self>(bytes)?.migrate()
+    }
+
+    /// The choices submitted within this vote
+    pub(crate) fn choices(&self) -> &[DecodedVoteChoice] {
+        &self.choices
+    }
+
+    /// The number of choices submitted within this vote
+    pub fn choice_count(&self) -> usize {
+        self.choices.len()
+    }
+
+    /// Iterates over the choices submitted within this vote
+    pub fn choices_iter(&self) -> impl Iterator<Item = &DecodedVoteChoice> {
+        self.choices.iter()
+    }
+
+    /// Whether this vote includes the choice with ID `id`
+    pub fn contains_choice_id(&self, id: i64) -> bool {
+        self.choices.iter().any(|c| c.contest_choice.id == id)
+    }
+
+    /// Removes the choice with ID `id` from this vote, if selected
+    pub(crate) fn remove_choice_id(&mut self, id: i64) {
+        self.choices.retain(|c| c.contest_choice.id != id);
+    }
+
+    /// The ID of the voter who submitted this vote, if known
+    pub fn voter_id(&self) -> Option<&str> {
+        self.voter_id.as_deref()
+    }
+
+    /// Sets the ID of the voter who submitted this vote
+    pub fn with_voter_id(mut self, voter_id: &str) -> Self {
+        self.voter_id = Some(voter_id.to_owned());
+        self
+    }
+
+    /// When this vote was submitted, if known
+    pub fn submitted_at(&self) -> Option<DateTime<Utc>> {
+        self.submitted_at
+    }
+
+    /// Sets when this vote was submitted
+    pub fn with_submitted_at(mut self, submitted_at: DateTime<Utc>) -> Self {
+        self.submitted_at = Some(submitted_at);
+        self
+    }
+
+    /// The channel this vote came in through (e.g. web portal, paper scan
+    /// or SMS), if known
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Sets the channel this vote came in through
+    pub fn with_source(mut self, src: &str) -> Self {
+        self.source = Some(src.to_owned());
+        self
+    }
+
+}
+
+impl fmt::Display for FlatVote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Vote for contest #{} ({} choices)", self.contest, self.choices.len())
+    }
+}
+
+/// Factory to create a [FlatVote] directly, without going through a
+/// [DecodedContestVote]. Choices are not checked against the contest, votes
+/// for choices outside of it are counted as invalid.
+///
+/// ```
+/// use votes::{ContestChoice, DecodedVoteChoice, FlatVoteBuilder};
+///
+/// let vote = FlatVoteBuilder::for_contest(1)
+///     .add_choice(DecodedVoteChoice::new(ContestChoice::new(100, "Alice")))
+///     .voter_id("voter-1")
+///     .build();
+///
+/// assert_eq!(1, vote.choice_count());
+/// assert!(!vote.is_invalid());
+/// ```
+pub struct FlatVoteBuilder {
+    contest: ContestId,
+    choices: Vec<DecodedVoteChoice>,
+    voter_id: Option<String>,
+    is_explicit_invalid: bool,
+}
+
+impl FlatVoteBuilder {
+
+    /// Starts a valid vote with no choices for the contest with ID `contest_id`
+    pub fn for_contest(contest_id: impl Into<ContestId>) -> FlatVoteBuilder {
+        FlatVoteBuilder{
+            contest: contest_id.into(),
+            choices: vec![],
+            voter_id: None,
+            is_explicit_invalid: false,
+        }
+    }
+
+    /// Adds a single choice to the vote
+    pub fn add_choice(mut self, choice: DecodedVoteChoice) -> FlatVoteBuilder {
+        self.choices.push(choice);
+        self
+    }
+
+    /// Sets the ID of the voter who submitted the vote
+    pub fn voter_id(mut self, id: impl Into<String>) -> FlatVoteBuilder {
+        self.voter_id = Some(id.into());
+        self
+    }
+
+    /// Marks the vote as explicitly invalid
+    pub fn mark_invalid(mut self) -> FlatVoteBuilder {
+        self.is_explicit_invalid = true;
+        self
+    }
+
+    /// Builds the [FlatVote]
+    pub fn build(self) -> FlatVote {
+        FlatVote{
+            is_explicit_invalid: self.is_explicit_invalid,
+            choices: self.choices,
+            contest: self.contest,
+            voter_id: self.voter_id,
+            is_provisional: false,
+            submitted_at: None,
+            source: None,
+            schema_version: SCHEMA_VERSION,
+        }
+    }
+
 }
 
 impl Tally {
 
+    /// Create an empty [Tally] for `contest`, counted with the method of the
+    /// contest, see [Contest::tally_method]
     pub fn new(contest: &Contest) -> Self {
+        Self::with_capacity(contest, 0)
+    }
+
+    /// Same as [Tally::new], but pre-allocates room for `capacity` votes
+    pub fn with_capacity(contest: &Contest, capacity: usize) -> Self {
+        Self{
+            contest: contest.clone(),
+            votes: Vec::with_capacity(capacity),
+            method: None,
+        }
+    }
+
+    /// Loads votes for `contest` from a file, verifying its checksum if it has one.
+    /// 
+    /// With the `compression` feature, files ending in `.gz` are decompressed.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(
+        contest_id = %contest.id(),
+        num_votes = tracing::field::Empty,
+        elapsed = tracing::field::Empty,
+    )))]
+    pub fn load_from_file<P: AsRef<Path>>(path: P, contest: &Contest) -> Result<Self, Error> {
+        record_elapsed!();
+        let mut votes = Self::new(contest);
+        votes.read_votes(path, false)?;
+        record!("num_votes", votes.votes.len());
+        Ok(votes)
+    }
+
+    /// Loads votes for `contest` from a gzip-compressed file written by [Tally::save_to_gz]
+    #[cfg(feature = "compression")]
+    pub fn load_from_gz<P: AsRef<Path>>(path: P, contest: &Contest) -> Result<Self, Error> {
+        let mut votes = Self::new(contest);
+        votes.read_votes_from(BufReader::new(GzDecoder::new(File::open(path)?)), false)?;
+        Ok(votes)
+    }
+
+    /// Same as [Tally::load_from_file], but fails with
+    /// [Error::MissingChecksum] if the file does not end with a checksum
+    pub fn load_from_file_strict<P: AsRef<Path>>(path: P, contest: &Contest) -> Result<Self, Error> {
+        let mut votes = Self::new(contest);
+        votes.read_votes(path, true)?;
+        Ok(votes)
+    }
+
+    /// Same as [Tally::load_from_file], but pre-allocates room for
+    /// `capacity` votes to avoid repeated reallocations
+    pub fn load_from_file_with_capacity<P: AsRef<Path>>(path: P, contest: &Contest, capacity: usize) -> Result<Self, Error> {
+        let mut votes = Self::with_capacity(contest, capacity);
+        votes.read_votes(path, false)?;
+        Ok(votes)
+    }
+
+    /// Lazily reads the votes for `contest` from a file written by
+    /// [Tally::save_to_file], one at a time.
+    /// 
+    /// Votes are never collected in memory and a single line buffer is reused,
+    /// so this works for files of any size. Votes for other contests are
+    /// skipped, and checksums are verified as they are reached, yielding
+    /// [Error::ChecksumMismatch] if they don't match. Errors carry the file
+    /// name as context, and iteration stops after the first one.
+    /// 
+    /// With the `compression` feature, files ending in `.gz` are decompressed.
+    pub fn iter_from_file<P: AsRef<Path>>(path: P, contest: &Contest) -> Result<impl Iterator<Item = Result<FlatVote, Error>>, Error> {
+        Self::iter_votes(path.as_ref(), contest.id())
+    }
+
+    /// Same as [Tally::iter_from_file], chaining the votes of all the files
+    /// in `paths`. Files are only opened once the previous ones are done.
+    /// 
+    /// Errors are yielded as items carrying the file name as context, and
+    /// iteration carries on with the next file, so callers can decide whether
+    /// to abort or skip the file.
+    pub fn iter_from_files<P: AsRef<Path>>(paths: &[P], contest: &Contest) -> Result<impl Iterator<Item = Result<FlatVote, Error>>, Error> {
+        let paths: Vec<PathBuf> = paths.iter().map(|p| p.as_ref().to_path_buf()).collect();
+        let contest_id = contest.id();
+        Ok(paths.into_iter().flat_map(move |path| -> Box<dyn Iterator<Item = Result<FlatVote, Error>>> {
+            match Self::iter_votes(&path, contest_id) {
+                Ok(votes) => Box::new(votes),
+                Err(e) => Box::new(std::iter::once(Err(e))),
+            }
+        }))
+    }
+
+    /// Lazily reads the votes for `contest_id` from `path`, see [Tally::iter_from_file]
+    fn iter_votes(path: &Path, contest_id: ContestId) -> Result<impl Iterator<Item = Result<FlatVote, Error>>, Error> {
+        let context = format!("Failed to read `{}`", path.display());
+        let file = File::open(path)
+            .map_err(|e| Error::from(e).context(context.clone()))?;
+        #[cfg(feature = "compression")]
+        let mut reader: Box<dyn BufRead> = match path.extension().is_some_and(|ext| ext == "gz") {
+            true => Box::new(BufReader::new(GzDecoder::new(file))),
+            false => Box::new(BufReader::new(file)),
+        };
+        #[cfg(not(feature = "compression"))]
+        let mut reader = BufReader::new(file);
+
+        let mut line = String::new();
+        let mut hasher = Sha256::new();
+        let mut failed = false;
+        let mut next_vote = move || -> Result<Option<FlatVote>, Error> {
+            loop {
+                line.clear();
+                if reader.read_line(&mut line)? == 0 {
+                    return Ok(None);
+                }
+                let trimmed = line.trim_end_matches(['\n', '\r']);
+                if trimmed.starts_with(CHECKSUM_PREFIX) {
+                    let ChecksumLine{ checksum: expected } = serde_json::from_str(trimmed)?;
+                    let actual = format!("{:x}", std::mem::take(&mut hasher).finalize());
+                    if expected != actual {
+                        return Err(Error::ChecksumMismatch{ expected, actual });
+                    }
+                    continue;
+                }
+                hasher.update(trimmed.as_bytes());
+                hasher.update(b"\n");
+                let vote = serde_json::from_str::<FlatVote>(trimmed)?.migrate()?;
+                if vote.contest == contest_id {
+                    return Ok(Some(vote));
+                }
+            }
+        };
+
+        Ok(std::iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+            let next = next_vote().transpose();
+            failed = matches!(next, Some(Err(_)));
+            next.map(|vote| vote.map_err(|e| e.context(context.clone())))
+        }))
+    }
+
+    /// Loads votes for `contest` from several files into a single [Tally]
+    pub fn load_from_files<P: AsRef<Path>>(paths: &[P], contest: &Contest) -> Result<Self, Error> {
+        Self::load_from_files_with_discarded(paths, contest).map(|(votes, _)| votes)
+    }
+
+    /// Same as [Tally::load_from_files], but also returns the number of
+    /// votes that were discarded because they belong to a different contest
+    pub fn load_from_files_with_discarded<P: AsRef<Path>>(paths: &[P], contest: &Contest) -> Result<(Self, usize), Error> {
+        let mut votes = Self::new(contest);
+        let mut discarded = 0;
+        for path in paths {
+            discarded += votes.read_votes(path, false)?;
+        }
+        Ok((votes, discarded))
+    }
+
+    /// Loads votes for `contest` from a CSV file written by [Tally::save_to_csv]
+    /// 
+    /// Rows belonging to other contests are skipped.
+    pub fn load_from_csv<P: AsRef<Path>>(path: P, contest: &Contest) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        let mut votes = Self::new(contest);
+        // Skip the header row
+        for line in reader.lines().skip(1) {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let fields = csv::split_record(&line)?;
+            let [contest_id, voter_id, is_invalid, choice_ids] = fields.as_slice() else {
+                return Err(Error::CSV(format!("Expected 4 fields in `{}`", line)));
+            };
+            let contest_id = contest_id.parse().map(ContestId)
+                .map_err(|_| Error::CSV(format!("Invalid contest ID `{}`", contest_id)))?;
+            if contest_id != contest.id() {
+                continue;
+            }
+            let is_explicit_invalid: bool = is_invalid.parse()
+                .map_err(|_| Error::CSV(format!("Invalid flag `{}`", is_invalid)))?;
+            let choices = choice_ids.split(',')
+                .filter(|id| !id.is_empty())
+                .map(|id| {
+                    let id: i64 = id.trim().parse()
+                        .map_err(|_| Error::CSV(format!("Invalid choice ID `{}`", id)))?;
+                    contest.choices().iter()
+                        .find(|c| c.id == id)
+                        .map(|c| DecodedVoteChoice::new(c.clone()))
+                        .ok_or(Error::UnknownChoiceId(id))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            votes.add_vote(FlatVote{
+                is_explicit_invalid,
+                choices,
+                contest: contest_id,
+                voter_id: (!voter_id.is_empty()).then(|| voter_id.clone()),
+                is_provisional: false,
+                submitted_at: None,
+                source: None,
+                schema_version: SCHEMA_VERSION,
+            });
+        }
+        Ok(votes)
+    }
+
+    /// Loads votes for `contest` from a binary file written by [Tally::save_to_bin]
+    /// 
+    /// Binary files are not migrated, see [FlatVote::from_bytes].
+    #[cfg(feature = "bincode")]
+    pub fn load_from_bin<P: AsRef<Path>>(path: P, contest: &Contest) -> Result<Self, Error> {
+        use std::io::{ErrorKind, Read};
+        let file = File::open(path)?;
+        // Length prefixes are checked against what is left of the file, so a
+        // corrupted prefix can't make us allocate more than the file size
+        let mut remaining = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+        let mut votes = Self::new(contest);
+        let mut len = [0u8; 4];
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_exact(&mut len) {
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                res => res?,
+            }
+            let len = u32::from_le_bytes(len) as u64;
+            remaining = remaining.saturating_sub(4);
+            if len > remaining {
+                return Err(std::io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    format!("Vote length {} exceeds the {} bytes left in the file", len, remaining),
+                ).into());
+            }
+            remaining -= len;
+            buf.resize(len as usize, 0);
+            reader.read_exact(&mut buf)?;
+            votes.add_vote(FlatVote::from_bytes(&buf)?);
+        }
+        Ok(votes)
+    }
+
+    /// Count votes, including provisional votes or not depending on `mode`
+    /// 
+    /// Votes including a disqualified choice are not counted, as in [Tally::result].
+    pub fn result_with_mode(&self, mode: TallyMode) -> ContestResult {
+        self.count_excluding(&[], |votes| self.method().tally_with_mode(&self.contest, votes, mode))
+    }
+
+    /// Count the votes in the tally followed by `extra`, one at a time, and
+    /// return the same result as if they had all been added to the tally.
+    /// 
+    /// Extra votes are never collected, so they can be streamed from any
+    /// source, e.g. [Tally::iter_from_file]. See [IncrementalTally].
+    pub fn result_incremental<I: Iterator<Item = FlatVote>>(&self, extra: I) -> ContestResult {
+        let mut incremental = IncrementalTally::new(&self.contest);
+        for vote in &self.votes {
+            incremental.feed(vote);
+        }
+        for vote in extra {
+            incremental.feed(&vote);
+        }
+        incremental.snapshot()
+    }
+
+    /// Saves the running counts of the votes in the tally to `path` and
+    /// returns the number of votes saved.
+    /// 
+    /// Only the counts are written, not the votes. Counting can continue
+    /// later on from [Tally::restore_from_checkpoint].
+    pub fn checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<usize, Error> {
+        let mut partial = PartialTally::new(&self.contest);
+        for vote in &self.votes {
+            partial.feed(vote);
+        }
+        partial.checkpoint(path)
+    }
+
+    /// Loads the counts saved by [Tally::checkpoint] for `contest`, so that
+    /// the remaining votes can be counted without the ones already saved
+    pub fn restore_from_checkpoint<P: AsRef<Path>>(path: P, contest: &Contest) -> Result<PartialTally, Error> {
+        PartialTally::restore(path, contest)
+    }
+
+    /// Count votes in parallel and return the same result as [Tally::result]
+    /// 
+    /// Votes are split into chunks that are counted independently and then
+    /// merged. Only worth it for very large tallies. Only [PluralityAtLarge]
+    /// counts can be split, other methods count with [Tally::result].
+    #[cfg(feature = "rayon")]
+    pub fn result_parallel(&self) -> ContestResult {
+        use rayon::prelude::*;
+        if self.method.is_none() && self.contest.has_embedded_method() {
+            return self.result();
+        }
+        self.count_excluding(&[], |votes| {
+            let counts = votes.par_chunks(PARALLEL_CHUNK_SIZE)
+                .map(|chunk| VoteCounts::from_votes(&self.contest, chunk, TallyMode::default()))
+                .reduce(VoteCounts::default, VoteCounts::merge);
+            PluralityAtLarge::result_from_counts(&self.contest, counts)
+        })
+    }
+
+}
+
+impl<M: TallyMethod> Tally<M> {
+
+    /// Create an empty [Tally] for `contest`, counted with `method` instead
+    /// of the method of the contest
+    pub fn with_method(contest: &Contest, method: M) -> Self {
         Self{
             contest: contest.clone(),
             votes: Vec::new(),
+            method: Some(method),
         }
     }
 
+    /// The [TallyMethod] used to count votes
+    pub fn method(&self) -> &dyn TallyMethod {
+        match &self.method {
+            Some(method) => method,
+            None => self.contest.tally_method(),
+        }
+    }
+
+    /// The [Contest] being tallied
+    pub fn contest(&self) -> &Contest {
+        &self.contest
+    }
+
+    /// All submitted votes
+    pub fn votes(&self) -> &[FlatVote] {
+        &self.votes
+    }
+
     /// Sets all votes
     pub fn with_votes(self, votes: Vec<FlatVote>) -> Self {
         Self{
@@ -95,162 +1296,687 @@ impl Tally {
         }
     }
 
+    /// Adds several votes at once. As with [Tally::add_vote], votes for
+    /// other contests are left out.
+    pub fn add_votes(&mut self, votes: impl IntoIterator<Item = FlatVote>) {
+        let contest_id = self.contest.id();
+        self.votes.extend(votes.into_iter().filter(|v| v.contest == contest_id));
+    }
+
+    /// Same as [Tally::add_vote], but fails with [Error::ContestNotOpen]
+    /// unless the contest is in the [ContestPhase::Open] phase
+    pub fn add_vote_checked(&mut self, vote: FlatVote) -> Result<(), Error> {
+        if self.contest.phase() != ContestPhase::Open {
+            return Err(Error::ContestNotOpen);
+        }
+        self.add_vote(vote);
+        Ok(())
+    }
+
+    /// Removes all votes, keeping the contest
+    pub fn reset(&mut self) {
+        self.votes.clear();
+    }
+
+    /// Replaces all votes with `votes`
+    pub fn replace_votes(&mut self, votes: Vec<FlatVote>) {
+        self.votes = votes;
+    }
+
+    /// Whether any vote was submitted by the voter with ID `voter_id`, e.g.
+    /// to reject duplicate ballots
+    pub fn has_vote_from_voter(&self, voter_id: &str) -> bool {
+        self.votes.iter().any(|v| v.voter_id() == Some(voter_id))
+    }
+
+    /// Number of votes submitted by the voter with ID `voter_id`, for
+    /// contests that accept more than one vote per voter
+    pub fn vote_count_from_voter(&self, voter_id: &str) -> usize {
+        self.votes.iter().filter(|v| v.voter_id() == Some(voter_id)).count()
+    }
+
+    /// Number of votes per source, see [FlatVote::source]. Votes with an
+    /// unknown source are counted under `None`.
+    pub fn votes_by_source(&self) -> HashMap<Option<String>, usize> {
+        let mut counts = HashMap::new();
+        for v in &self.votes {
+            *counts.entry(v.source.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Number of provisional votes
+    pub fn provisional_count(&self) -> usize {
+        self.votes.iter().filter(|v| v.is_provisional).count()
+    }
+
+    /// The votes counted as valid by [Tally::result]: those that are not
+    /// invalid, provisional or for a choice outside the contest
+    pub fn valid_votes(&self) -> impl Iterator<Item = &FlatVote> {
+        self.votes.iter().filter(|v| {
+            !v.is_invalid() && !v.is_provisional && InvalidVoteBreakdown::choices_in_contest(&self.contest, v)
+        })
+    }
+
+    /// The votes that are invalid, including those for a choice outside the
+    /// contest
+    pub fn invalid_votes(&self) -> impl Iterator<Item = &FlatVote> {
+        self.votes.iter().filter(|v| {
+            v.is_invalid() || !InvalidVoteBreakdown::choices_in_contest(&self.contest, v)
+        })
+    }
+
+    /// The valid votes that selected the choice with ID `choice_id`, skipping
+    /// provisional votes like [Tally::count_for]
+    pub fn votes_for_choice_id(&self, choice_id: i64) -> Vec<&FlatVote> {
+        self.valid_votes()
+            .filter(|v| v.choices.iter().any(|c| c.contest_choice.id == choice_id && c.selected > 0))
+            .collect()
+    }
+
+    /// Number of votes counted as valid, see [Tally::valid_votes]
+    pub fn valid_vote_count(&self) -> usize {
+        self.valid_votes().count()
+    }
+
+    /// Number of invalid votes
+    pub fn invalid_vote_count(&self) -> usize {
+        self.invalid_votes().count()
+    }
+
     /// Saves votes to a file and returns the filename
+    /// 
+    /// Votes are written one per line, followed by a `{"__checksum":"..."}`
+    /// line with the SHA-256 hash of all vote lines.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(
+        contest_id = %self.contest.id(),
+        num_votes = self.votes.len(),
+        elapsed = tracing::field::Empty,
+    )))]
     pub fn save_to_file(&self) -> Result<String, Error> {
-        let fname = format!("votes-{}.json", self.contest.id());
-        let mut file = File::create(&fname)?;
+        record_elapsed!();
+        self.save_to_file_with(&DefaultNaming)
+    }
+
+    /// Same as [Tally::save_to_file], with the file named by `naming`
+    pub fn save_to_file_with(&self, naming: &dyn FileNamingStrategy) -> Result<String, Error> {
+        let fname = naming.file_name("votes", self.contest.id().0);
+        fs::write_atomically(&fname, |file| self.write_votes(file))?;
+        Ok(fname)
+    }
+
+    /// Saves votes to a gzip-compressed file, in the same format as [Tally::save_to_file]
+    #[cfg(feature = "compression")]
+    pub fn save_to_gz<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        fs::write_atomically(path, |file| {
+            let mut encoder = GzEncoder::new(std::io::BufWriter::new(file), Compression::default());
+            self.write_votes(&mut encoder)?;
+            encoder.finish()?.flush()?;
+            Ok(())
+        })
+    }
+
+    /// Writes all votes one per line, followed by the checksum line
+    fn write_votes<W: Write>(&self, out: &mut W) -> Result<(), Error> {
+        let mut hasher = Sha256::new();
         for v in &self.votes {
-            let serialized: String = serde_json::to_string(v)?;
-            file.write_all(serialized.as_bytes())?; 
-            file.write_all("\n".as_bytes())?;
+            let serialized: String = serde_json::to_string(v)? + "\n";
+            out.write_all(serialized.as_bytes())?; 
+            hasher.update(serialized.as_bytes());
         }
-        file.flush()?;
-        Ok(fname)
+        let checksum = ChecksumLine{ checksum: format!("{:x}", hasher.finalize()) };
+        out.write_all(serde_json::to_string(&checksum)?.as_bytes())?;
+        out.write_all("\n".as_bytes())?;
+        Ok(())
     }
 
-    /// Loads votes for `contest` from a file
-    pub fn load_from_file<P: AsRef<Path>>(path: P, contest: &Contest) -> Result<Self, Error> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let mut votes = Self::new(contest);
-        for line in reader.lines().map_while(Result::ok) {
-            let vote: FlatVote = serde_json::from_str(&line)?;
-            votes.add_vote(vote);
+    /// Appends the votes in a file and returns how many were discarded
+    /// 
+    /// If the file has a checksum line, the votes before it are verified
+    /// against it. In `strict` mode, the file must end with a checksum line.
+    /// 
+    /// Files with a `.gz` extension are decompressed on the fly when the
+    /// `compression` feature is enabled.
+    fn read_votes<P: AsRef<Path>>(&mut self, path: P, strict: bool) -> Result<usize, Error> {
+        let path = path.as_ref();
+        let mut read = || -> Result<usize, Error> {
+            let file = File::open(path)?;
+            #[cfg(feature = "compression")]
+            if path.extension().is_some_and(|ext| ext == "gz") {
+                return self.read_votes_from(BufReader::new(GzDecoder::new(file)), strict);
+            }
+            self.read_votes_from(BufReader::new(file), strict)
+        };
+        read().map_err(|e| e.context(format!("Failed to read `{}`", path.display())))
+    }
+
+    /// Same as [Tally::read_votes], for any reader
+    fn read_votes_from<R: BufRead>(&mut self, reader: R, strict: bool) -> Result<usize, Error> {
+        let mut discarded = 0;
+        let mut hasher = Sha256::new();
+        let mut verified = false;
+        for line in reader.lines() {
+            let line = line?;
+            if line.starts_with(CHECKSUM_PREFIX) {
+                let ChecksumLine{ checksum: expected } = serde_json::from_str(&line)?;
+                let actual = format!("{:x}", std::mem::take(&mut hasher).finalize());
+                if expected != actual {
+                    return Err(Error::ChecksumMismatch{ expected, actual });
+                }
+                verified = true;
+                continue;
+            }
+            hasher.update(line.as_bytes());
+            hasher.update(b"\n");
+            verified = false;
+            let vote = serde_json::from_str::<FlatVote>(&line)?.migrate()?;
+            if vote.contest != self.contest.id() {
+                discarded += 1;
+            }
+            self.add_vote(vote);
         }
-        Ok(votes)
+        if strict && !verified {
+            return Err(Error::MissingChecksum);
+        }
+        Ok(discarded)
+    }
+
+    /// Saves votes to a CSV file with one row per vote.
+    /// 
+    /// Columns are `contest_id,voter_id,is_invalid,choice_ids`, where
+    /// `choice_ids` is a quoted, comma-separated list. Vote weights are not
+    /// preserved: every choice is loaded back with `selected` set to 1.
+    pub fn save_to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        fs::write_atomically(path, |file| {
+            file.write_all("contest_id,voter_id,is_invalid,choice_ids\n".as_bytes())?;
+            for v in &self.votes {
+                let choice_ids: Vec<String> = v.choices.iter()
+                    .map(|c| c.contest_choice.id.to_string())
+                    .collect();
+                let row = format!("{},{},{},{}\n",
+                    v.contest,
+                    csv::escape_field(v.voter_id.as_deref().unwrap_or_default()),
+                    v.is_explicit_invalid,
+                    csv::quote_field(&choice_ids.join(",")),
+                );
+                file.write_all(row.as_bytes())?;
+            }
+            Ok(())
+        })
     }
 
-    /// Count votes and return 
+    /// Count votes and return the result of the [TallyMethod]
+    /// 
+    /// With [PluralityAtLarge], provisional votes are not counted, see
+    /// [Tally::result_with_mode]. Votes including a choice disqualified with
+    /// [Contest::disqualify_choice] are not counted either.
+    /// 
+    /// [ContestResult::calculated_at] is set to when counting started.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(
+        contest_id = %self.contest.id(),
+        num_votes = self.votes.len(),
+        elapsed = tracing::field::Empty,
+    )))]
     pub fn result(&self) -> ContestResult {
+        record_elapsed!();
+        let calculated_at = Utc::now();
+        let mut result = self.result_excluding(&[]);
+        result.calculated_at = calculated_at;
+        debug!(
+            valid = result.total_valid_votes,
+            invalid = result.total_invalid_votes,
+            winners = result.winners.len(),
+            "Counted votes",
+        );
+        result
+    }
 
-        let mut invalid_votes: i64 = 0;
-        let mut counts = HashMap::new();
+    /// Count votes again, ignoring every vote that includes one of
+    /// `excluded_ids` or a choice disqualified in the contest.
+    /// 
+    /// The excluded choices are listed in [ContestResult::disqualified_choices].
+    pub fn result_excluding(&self, excluded_ids: &[i64]) -> ContestResult {
+        self.count_excluding(excluded_ids, |votes| self.method().tally(&self.contest, votes))
+    }
 
-        for vote in &self.votes {
+    /// Runs `count` on the votes that don't include one of `excluded_ids`
+    /// or a choice disqualified in the contest, see [Tally::result_excluding]
+    fn count_excluding<F>(&self, excluded_ids: &[i64], count: F) -> ContestResult
+    where F: FnOnce(&[FlatVote]) -> ContestResult {
+        let excluded: Vec<&ContestChoice> = self.contest.choices().iter()
+            .filter(|c| excluded_ids.contains(&c.id) || self.contest.disqualified_choice_ids().contains(&c.id))
+            .collect();
+        if excluded.is_empty() {
+            return count(&self.votes);
+        }
 
-            // Skip invalid votes
-            if vote.is_explicit_invalid {
-                invalid_votes += 1;
-                continue;
+        let votes: Vec<FlatVote> = self.votes.iter()
+            .filter(|v| !excluded.iter().any(|c| v.contains_choice_id(c.id)))
+            .cloned()
+            .collect();
+        let mut result = count(&votes);
+        result.disqualified_choices = excluded.into_iter().cloned().collect();
+        result
+    }
+
+    /// Same as [Tally::result], but winners with fewer than `min_votes`
+    /// votes are not declared winners. Their `winner_position` is reset to
+    /// zero and they are counted in [ContestResult::below_threshold_count].
+    /// 
+    /// Only the last winners can fall below the threshold, so the remaining
+    /// ones keep their positions. The ranking itself is unchanged: the margin
+    /// of victory, and [ContestResult::is_decisive] with it, still compare
+    /// the two choices with the most votes, whether they won or not.
+    pub fn result_with_threshold(&self, min_votes: u64) -> ContestResult {
+        Self::apply_threshold(self.result(), |r| r.total_count >= min_votes)
+    }
+
+    /// Same as [Tally::result_with_threshold], with the threshold given as a
+    /// percentage (`0.0` to `100.0`) of the total valid votes
+    pub fn result_with_pct_threshold(&self, min_pct: f64) -> ContestResult {
+        let result = self.result();
+        let total = result.total_valid_votes;
+        Self::apply_threshold(result, |r| match total {
+            0 => false,
+            total => r.total_count as f64 / total as f64 * 100.0 >= min_pct,
+        })
+    }
+
+    /// Removes the winners whose results don't pass `meets_threshold`
+    fn apply_threshold<F>(mut result: ContestResult, meets_threshold: F) -> ContestResult
+    where F: Fn(&ContestChoiceResult) -> bool {
+        let mut below: Vec<i64> = vec![];
+        for r in result.results.iter_mut().filter(|r| r.winner_position > 0) {
+            if !meets_threshold(r) {
+                r.winner_position = 0;
+                below.push(r.contest_choice.id);
             }
+        }
+        result.winners.retain(|w| !below.contains(&w.id));
+        result.below_threshold_count = below.len() as u64;
+        result
+    }
 
-            // Count all choices for this voter
-            for choice in &vote.choices {
-                if choice.selected > 0 {
-                    counts.entry(choice.contest_choice.id)
-                        .and_modify(|e| *e += choice.selected)
-                        .or_insert(choice.selected);
-                }
+    /// Saves votes to a binary file, with every vote prefixed by its length
+    /// as a little-endian `u32`. See [FlatVote::to_bytes].
+    #[cfg(feature = "bincode")]
+    pub fn save_to_bin<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        fs::write_atomically(path, |file| {
+            let mut file = std::io::BufWriter::new(file);
+            for v in &self.votes {
+                let bytes = v.to_bytes()?;
+                file.write_all(&(bytes.len() as u32).to_le_bytes())?;
+                file.write_all(&bytes)?;
             }
+            file.flush()?;
+            Ok(())
+        })
+    }
 
-        }
+    /// Count the votes for a single choice without ranking the rest.
+    /// 
+    /// `winner_position` is always zero since it requires a full tally.
+    /// Returns `None` if the choice is not part of the contest.
+    pub fn result_for_choice(&self, choice_id: i64) -> Option<ContestChoiceResult> {
+        let choice = self.contest.choice_by_id(choice_id)?;
+        Some(ContestChoiceResult{
+            contest_choice: choice.clone(),
+            total_count: self.count_for(choice_id),
+            winner_position: 0,
+        })
+    }
 
-        // Calculate winners
-        let mut sorted_results: Vec<(i64, u64)> = counts.into_iter().collect();
-        sorted_results.sort_by_key(|(_, votes)| std::cmp::Reverse(*votes));
+    /// Total votes for a single choice, skipping invalid and provisional votes
+    pub fn count_for(&self, choice_id: i64) -> u64 {
+        self.votes.iter()
+            .filter(|v| !v.is_explicit_invalid && !v.is_provisional)
+            .flat_map(|v| &v.choices)
+            .filter(|c| c.contest_choice.id == choice_id)
+            .map(|c| c.selected)
+            .sum()
+    }
 
-        // Calculate positions
-        let positions = Self::calc_positions(&sorted_results, self.contest.num_winners());
+    /// Total votes for every choice that received any, skipping invalid and
+    /// provisional votes
+    pub fn counts_all(&self) -> HashMap<i64, u64> {
+        VoteCounts::from_votes(&self.contest, &self.votes, TallyMode::default()).counts
+    }
 
-        // Accumulate results for each choice as per exercise requirements
-        let results = sorted_results.iter().map(|(choice_id, vote_count)| {
-            let choice = self.contest.choices().iter()
-                .find(|c| c.id == *choice_id)
-                .expect("Got a vote for a choice that's not part of the contest");
-            let pos = positions.iter()
-                .find(|p| p.0 == *choice_id)
-                .map(|p| p.1)
-                .unwrap_or(0);
-            ContestChoiceResult{
-                contest_choice: choice.clone(),
-                total_count: *vote_count,
-                winner_position: pos as u64,
-            }
-        }).collect();
+    /// Same as [Tally::counts_all]: only the counting loop of
+    /// [Tally::result], without ranking the choices
+    pub fn count(&self) -> HashMap<i64, u64> {
+        self.counts_all()
+    }
 
-        // Fill in the winners metadata
-        let cutoff = (self.contest.num_winners() as usize).min(sorted_results.len());
-        let winners = sorted_results[..cutoff].to_vec();
-        let winners = winners.into_iter().map(|(choice_id, _)| {
-            self.contest.choices().iter().find(|c| c.id == choice_id)
-                .expect("Failed to find winner choice")
-                .clone()
-        }).collect();
+    /// Same as [Tally::count], sorted by descending vote count with ties by
+    /// choice ID.
+    /// 
+    /// This is the ranking of [Tally::result] for the choices listed, but
+    /// choices without votes are left out and votes for disqualified choices
+    /// are still counted, as in [Tally::counts_all].
+    pub fn count_sorted(&self) -> Vec<(i64, u64)> {
+        let mut counts: Vec<(i64, u64)> = self.count().into_iter().collect();
+        counts.sort_by_key(|(choice_id, votes)| (std::cmp::Reverse(*votes), *choice_id));
+        counts
+    }
 
-        ContestResult{
-           contest: self.contest.clone(),
-           total_valid_votes: self.votes.len() as i64 - invalid_votes,
-           total_invalid_votes: invalid_votes,
-           results,
-           winners, 
-        }
+    /// Count only the votes submitted up to `cutoff` (inclusive).
+    /// 
+    /// Votes without a submission time are always counted.
+    pub fn result_as_of(&self, cutoff: DateTime<Utc>) -> ContestResult {
+        let votes: Vec<FlatVote> = self.votes.iter()
+            .filter(|v| !matches!(v.submitted_at, Some(t) if t > cutoff))
+            .cloned()
+            .collect();
+        self.method().tally(&self.contest, &votes)
+    }
 
+    /// Count votes as if `extra` had also been submitted, leaving the tally
+    /// untouched. Extra votes for other contests are ignored.
+    pub fn simulate_with_extra_votes(&self, extra: Vec<FlatVote>) -> ContestResult {
+        let contest_id = self.contest.id();
+        let votes: Vec<FlatVote> = self.votes.iter()
+            .cloned()
+            .chain(extra.into_iter().filter(|v| v.contest == contest_id))
+            .collect();
+        self.method().tally(&self.contest, &votes)
     }
 
-    /// Calculate positions taking into account potential ties
-    /// Receives a sorted array of participants with their votes and
-    /// returns an array of participants with their positions
-    fn calc_positions(sorted_votes: &[(i64, u64)], num_winners: i64) -> Vec<(i64, i64)> {
-    
-        if sorted_votes.is_empty(){
-            return vec![];
-        }
+    /// The fewest extra votes for `choice_id` alone that would place it at
+    /// `target_position` or better (zero if it already is).
+    /// 
+    /// Returns `None` if the choice is not part of the contest or the
+    /// position is not a winning one.
+    pub fn votes_needed_for_choice_to_reach_position(&self, choice_id: i64, target_position: u64) -> Option<u64> {
 
-        let mut current_position = 1;
-        let mut current_votes = sorted_votes[0].1;
+        let choice = self.contest.choice_by_id(choice_id)?;
+        if target_position == 0 || target_position > self.contest.num_winners().max(0) as u64 {
+            return None;
+        }
 
-        let mut positions = vec![(sorted_votes[0].0, current_position)];
+        let reaches_target = |extra: u64| {
+            let votes = match extra {
+                0 => vec![],
+                n => vec![FlatVote{
+                    is_explicit_invalid: false,
+                    choices: vec![DecodedVoteChoice::with_weight(choice.clone(), n)],
+                    contest: self.contest.id(),
+                    voter_id: None,
+                    is_provisional: false,
+                    submitted_at: None,
+                    source: None,
+                    schema_version: SCHEMA_VERSION,
+                }],
+            };
+            let result = self.simulate_with_extra_votes(votes);
+            result.choice_result_for(choice_id)
+                .is_some_and(|r| r.winner_position > 0 && r.winner_position <= target_position)
+        };
 
-        for &vote in &sorted_votes[1..] {
-            if positions.len() >= num_winners as usize {
-                positions.push((vote.0, 0));
+        // Outvoting the current leader is always enough
+        let mut low = 0;
+        let mut high = self.counts_all().into_values().max().unwrap_or(0) + 1;
+        if !reaches_target(high) {
+            return None;
+        }
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if reaches_target(mid) {
+                high = mid;
             } else {
-                if vote.1 != current_votes {
-                    current_position += 1;
-                    current_votes = vote.1;
+                low = mid + 1;
+            }
+        }
+        Some(low)
+
+    }
+
+    /// Pairwise preferences between choices: for every ordered pair `(a, b)`,
+    /// the number of voters who ranked `a` above `b`.
+    /// 
+    /// Ranks are taken from [DecodedVoteChoice::selected] (1 = first
+    /// preference). Ranked choices are preferred over unranked ones. Invalid
+    /// and provisional votes are skipped.
+    pub fn condorcet_matrix(&self) -> HashMap<(i64, i64), u64> {
+        let choice_ids: Vec<i64> = self.contest.choices().iter().map(|c| c.id).collect();
+        let mut matrix = HashMap::new();
+        for vote in self.votes.iter().filter(|v| !v.is_explicit_invalid && !v.is_provisional) {
+            let ranks: Vec<u64> = choice_ids.iter().map(|id| {
+                vote.choices.iter()
+                    .filter(|c| c.contest_choice.id == *id && c.selected > 0)
+                    .map(|c| c.selected)
+                    .min()
+                    .unwrap_or(u64::MAX)
+            }).collect();
+            for (a, rank_a) in choice_ids.iter().zip(&ranks) {
+                for (b, rank_b) in choice_ids.iter().zip(&ranks) {
+                    if rank_a < rank_b {
+                        *matrix.entry((*a, *b)).or_insert(0) += 1;
+                    }
                 }
-                positions.push((vote.0, current_position));    
             }
         }
+        matrix
+    }
 
-        positions
+    /// The choice that beats every other choice in pairwise comparisons (see
+    /// [Tally::condorcet_matrix]), if there is one
+    pub fn condorcet_winner(&self) -> Option<&ContestChoice> {
+        let matrix = self.condorcet_matrix();
+        let wins = |a: i64, b: i64| matrix.get(&(a, b)).copied().unwrap_or(0);
+        self.contest.choices().iter().find(|a| {
+            self.contest.choices().iter()
+                .filter(|b| b.id != a.id)
+                .all(|b| wins(a.id, b.id) > wins(b.id, a.id))
+        })
+    }
+
+}
+
+impl<M: TallyMethod + Clone> Tally<M> {
+
+    /// Returns a new [Tally] for the same contest with only the votes for
+    /// which `predicate` returns `true`
+    pub fn filter_votes<F>(&self, predicate: F) -> Self
+    where F: Fn(&FlatVote) -> bool {
+        Self{
+            contest: self.contest.clone(),
+            votes: self.votes.iter().filter(|v| predicate(v)).cloned().collect(),
+            method: self.method.clone(),
+        }
+    }
 
+    /// Splits the votes into two new [Tally]s for the same contest: the
+    /// votes for which `predicate` returns `true` and the rest
+    pub fn partition_votes<F>(&self, predicate: F) -> (Self, Self)
+    where F: Fn(&FlatVote) -> bool {
+        let (matching, rest) = self.votes.iter().cloned().partition(|v| predicate(v));
+        (
+            Self{
+                contest: self.contest.clone(),
+                votes: matching,
+                method: self.method.clone(),
+            },
+            Self{
+                contest: self.contest.clone(),
+                votes: rest,
+                method: self.method.clone(),
+            },
+        )
     }
 
 }
 
+impl<'a, M: TallyMethod> IntoIterator for &'a Tally<M> {
+    type Item = &'a FlatVote;
+    type IntoIter = std::slice::Iter<'a, FlatVote>;
 
-#[test]
-fn test_calc_positions() {
+    fn into_iter(self) -> Self::IntoIter {
+        self.votes.iter()
+    }
+}
 
-    let votes = vec![
-        (100, 10),
-        (200, 6),
-        (300, 6),
-        (400, 4),
-        (500, 1),
-        (600, 1),
-    ];
+impl<'a, M: TallyMethod> IntoIterator for &'a mut Tally<M> {
+    type Item = &'a mut FlatVote;
+    type IntoIter = std::slice::IterMut<'a, FlatVote>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.votes.iter_mut()
+    }
+}
+
+/// Trailer line written after all votes by [Tally::save_to_file], holding
+/// the SHA-256 hash of every preceding line
+#[derive(Serialize, Deserialize)]
+struct ChecksumLine {
+    #[serde(rename = "__checksum")]
+    checksum: String,
+}
 
-    let positions = Tally::calc_positions(&votes, 5);
+/// How a [ChecksumLine] starts, to tell it apart from votes
+const CHECKSUM_PREFIX: &str = "{\"__checksum\"";
 
-    assert_eq!(1, positions[0].1);
-    assert_eq!(2, positions[1].1);
-    assert_eq!(2, positions[2].1);
-    assert_eq!(3, positions[3].1);
-    assert_eq!(4, positions[4].1);
-    // Last one should be zero because there's
-    // only 5 winners in this contest
-    assert_eq!(0, positions[5].1);
+/// Number of votes counted by each worker in [Tally::result_parallel]
+#[cfg(feature = "rayon")]
+const PARALLEL_CHUNK_SIZE: usize = 10_000;
+
+/// Per-choice vote counts accumulated while tallying
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct VoteCounts {
+    pub(crate) counts: HashMap<i64, u64>,
+    pub(crate) valid: i64,
+    pub(crate) invalid: i64,
+    pub(crate) provisional: u64,
+    pub(crate) invalid_breakdown: InvalidVoteBreakdown,
+}
+
+impl VoteCounts {
+
+    pub(crate) fn from_votes<'a>(contest: &Contest, votes: impl IntoIterator<Item = &'a FlatVote>, mode: TallyMode) -> Self {
+        debug!(contest_id = %contest.id(), ?mode, "Counting votes");
+        let mut counts = Self::default();
+        for vote in votes {
+            counts.add(contest, vote, mode);
+        }
+        counts
+    }
+
+    /// Counts a single vote for `contest`
+    pub(crate) fn add(&mut self, contest: &Contest, vote: &FlatVote, mode: TallyMode) {
+
+        // Provisional votes are always reported, but only counted on demand
+        if vote.is_provisional {
+            self.provisional += 1;
+            if mode == TallyMode::ExcludeProvisional {
+                return;
+            }
+        }
+
+        // Skip invalid votes, including those for choices outside the contest
+        if vote.is_explicit_invalid || !InvalidVoteBreakdown::choices_in_contest(contest, vote) {
+            debug!(contest_id = %contest.id(), voter_id = vote.voter_id(), "Skipping invalid vote");
+            self.invalid += 1;
+            self.invalid_breakdown.add(contest, vote);
+            return;
+        }
+        self.valid += 1;
+
+        // Count all choices for this voter
+        for choice in &vote.choices {
+            if choice.selected > 0 {
+                self.counts.entry(choice.contest_choice.id)
+                    .and_modify(|e| *e += choice.selected)
+                    .or_insert(choice.selected);
+            }
+        }
+
+    }
+
+    /// Merges two partial counts together
+    #[cfg(feature = "rayon")]
+    fn merge(mut self, other: Self) -> Self {
+        for (choice_id, count) in other.counts {
+            *self.counts.entry(choice_id).or_insert(0) += count;
+        }
+        self.valid += other.valid;
+        self.invalid += other.invalid;
+        self.provisional += other.provisional;
+        self.invalid_breakdown = self.invalid_breakdown.merge(other.invalid_breakdown);
+        self
+    }
 
 }
 
 impl From<DecodedContestVote> for FlatVote {
     fn from(value: DecodedContestVote) -> Self {
         Self{
-            is_explicit_invalid: value.is_explicit_invalid,
+            is_explicit_invalid: value.is_invalid(),
+            choices: value.choices,
+            contest: value.contest.id(),
+            voter_id: None,
+            is_provisional: false,
+            submitted_at: None,
+            source: None,
+            schema_version: SCHEMA_VERSION,
+        }
+    }
+}
+
+impl From<&DecodedContestVote> for FlatVote {
+    fn from(value: &DecodedContestVote) -> Self {
+        Self{
+            is_explicit_invalid: value.is_invalid(),
             choices: value.choices.clone(),
             contest: value.contest.id(),
+            voter_id: None,
+            is_provisional: false,
+            submitted_at: None,
+            source: None,
+            schema_version: SCHEMA_VERSION,
+        }
+    }
+}
+
+/// Rebuilds the full vote, taking every choice from the [Contest].
+/// 
+/// Fails with [Error::ContestMismatch] if the vote was emitted for another
+/// contest, and with [Error::UnknownChoiceId] if a choice is not part of the
+/// contest. Write-in choices are kept as they are.
+/// 
+/// [DecodedContestVote] has no room for the voter ID, provisional status,
+/// submission time or source of the flat vote, so they are dropped.
+impl TryFrom<(FlatVote, &Contest)> for DecodedContestVote {
+    type Error = Error;
+
+    fn try_from((vote, contest): (FlatVote, &Contest)) -> Result<Self, Self::Error> {
+        if vote.contest != contest.id() {
+            return Err(Error::ContestMismatch{
+                expected: contest.id(),
+                actual: vote.contest,
+            });
         }
+        let choices = vote.choices.into_iter()
+            .map(|choice| {
+                if choice.contest_choice.is_write_in() {
+                    return Ok(choice);
+                }
+                let id = choice.contest_choice.id;
+                let contest_choice = contest.choice_by_id(id)
+                    .ok_or(Error::UnknownChoiceId(id))?
+                    .clone();
+                Ok(DecodedVoteChoice{ contest_choice, ..choice })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        // Flat votes don't keep the reason, so it is worked out again
+        let validity = match (vote.is_explicit_invalid, DecodedContestVote::check(contest, &choices)) {
+            (false, _) => VoteValidity::Valid,
+            (true, VoteValidity::Valid) => VoteValidity::Invalid(InvalidReason::ExplicitlyInvalidated),
+            (true, validity) => validity,
+        };
+        Ok(Self{
+            validity,
+            choices,
+            contest: contest.clone(),
+        })
     }
 }
@@ -0,0 +1,49 @@
+// trace.rs
+
+//! Instrumentation helpers. They expand to nothing unless the `tracing`
+//! feature is enabled, so that call sites don't need their own `cfg`s.
+
+/// Emits a [tracing::debug!] event
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    };
+}
+
+/// Records `value` as the `field` of the current span
+macro_rules! record {
+    ($field:literal, $value:expr) => {
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record($field, $value);
+    };
+}
+
+/// Records the time until the end of the enclosing block as the `elapsed`
+/// field of the current span
+macro_rules! record_elapsed {
+    () => {
+        #[cfg(feature = "tracing")]
+        let _elapsed = crate::trace::Elapsed::start();
+    };
+}
+
+/// Guard behind [record_elapsed!], recording the elapsed time when dropped
+#[cfg(feature = "tracing")]
+pub(crate) struct Elapsed(std::time::Instant);
+
+#[cfg(feature = "tracing")]
+impl Elapsed {
+
+    pub(crate) fn start() -> Self {
+        Self(std::time::Instant::now())
+    }
+
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for Elapsed {
+    fn drop(&mut self) {
+        tracing::Span::current().record("elapsed", tracing::field::debug(self.0.elapsed()));
+    }
+}
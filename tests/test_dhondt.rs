@@ -0,0 +1,50 @@
+// test_dhondt.rs
+
+use std::collections::HashMap;
+use votes::{ContestBuilder, ContestChoice, DecodedContestVote, DecodedVoteChoice, FlatVote, Tally};
+use votes::DHondtTally;
+
+
+#[test]
+fn dhondt_seat_allocation() {
+
+    // Two candidates for party A, one for each of the rest
+    let choices = vec![
+        ContestChoice::new(1, "Alice"),
+        ContestChoice::new(2, "Anne"),
+        ContestChoice::new(3, "Bob"),
+        ContestChoice::new(4, "Carol"),
+        ContestChoice::new(5, "Dave"),
+    ];
+    let contest = ContestBuilder::new(8, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+    let party_of: HashMap<i64, String> = [(1, "A"), (2, "A"), (3, "B"), (4, "C"), (5, "D")].iter()
+        .map(|(id, party)| (*id, party.to_string()))
+        .collect();
+
+    // A: 100k, B: 80k, C: 30k, D: 20k, with weighted votes to keep it small
+    let votes: Vec<FlatVote> = [(0, 60_000), (1, 40_000), (2, 80_000), (3, 30_000), (4, 20_000)].iter()
+        .map(|(i, weight)| {
+            let choice = DecodedVoteChoice::with_weight(choices[*i].clone(), *weight);
+            DecodedContestVote::new(&contest, vec![choice]).into()
+        })
+        .collect();
+
+    let dhondt = DHondtTally::new(Tally::new(&contest).with_votes(votes), party_of);
+    assert_eq!(100_000, dhondt.party_votes()["A"]);
+
+    let result = dhondt.result(8);
+    assert_eq!(4, result.seats_by_party["A"]);
+    assert_eq!(3, result.seats_by_party["B"]);
+    assert_eq!(1, result.seats_by_party["C"]);
+    assert_eq!(0, result.seats_by_party["D"]);
+
+    // Every seat is traced, highest quotients first
+    let parties: Vec<&str> = result.rounds.iter().map(|r| r.party.as_str()).collect();
+    assert_eq!(vec!["A", "B", "A", "B", "A", "C", "B", "A"], parties);
+    assert_eq!(100_000.0, result.rounds[0].quotient);
+    assert_eq!(25_000.0, result.rounds[7].quotient);
+
+}
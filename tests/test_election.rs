@@ -0,0 +1,84 @@
+// test_election.rs
+
+use std::fs;
+use votes::{ContestId, Election, ElectionTally};
+use votes::{gen_random_choices, gen_random_contest, gen_random_votes};
+
+
+#[test]
+fn test_election_results() {
+
+    // Generate an election with three contests
+    let contests = vec![
+        gen_random_contest(1, gen_random_choices(5)),
+        gen_random_contest(3, gen_random_choices(10)),
+        gen_random_contest(2, gen_random_choices(4)),
+    ];
+    let mut election = Election::new(&contests);
+
+    // Submit votes for every contest
+    for contest in &contests {
+        for vote in gen_random_votes(50, contest) {
+            election.add_vote(vote);
+        }
+    }
+
+    // Every contest gets its own tally and result
+    for contest in &contests {
+        let tally = election.tally_for(contest.id())
+            .expect("Failed to find tally for contest");
+        assert_eq!(50, tally.votes().len());
+        let result = election.result_for(contest.id())
+            .expect("Failed to find result for contest");
+        assert_eq!(contest.id(), result.contest.id());
+    }
+    assert_eq!(3, election.all_results().len());
+
+    // Unknown contests have no tally
+    assert!(election.tally_for(ContestId(-1)).is_none());
+    assert!(election.result_for(ContestId(-1)).is_none());
+
+}
+
+
+#[test]
+fn test_io_election() {
+
+    // Generate an election with two contests
+    let contests = vec![
+        gen_random_contest(1, gen_random_choices(5)),
+        gen_random_contest(3, gen_random_choices(10)),
+    ];
+    let mut election = Election::new(&contests);
+    for contest in &contests {
+        for vote in gen_random_votes(20, contest) {
+            election.add_vote(vote);
+        }
+    }
+
+    // Save contests and votes
+    let election_file = election.save_to_file()
+        .expect("Failed to save election");
+    let votes_file = election.tally().save_to_file()
+        .expect("Failed to save election votes");
+
+    // Load them back
+    let loaded = Election::load_from_file(&election_file)
+        .expect("Failed to load election");
+    let loaded_tally = ElectionTally::load_from_file(&votes_file, &loaded)
+        .expect("Failed to load election votes");
+    let loaded = loaded.with_tally(loaded_tally);
+
+    // Make sure they match
+    assert_eq!(election.id(), loaded.id());
+    for contest in &contests {
+        assert_eq!(election.tally_for(contest.id()), loaded.tally_for(contest.id()));
+    }
+
+    // Remove tmp files
+    fs::remove_file(&election_file)
+        .expect("Failed to remove file after test");
+    fs::remove_file(&votes_file)
+        .expect("Failed to remove file after test");
+
+}
@@ -2,7 +2,8 @@
 
 use std::fs;
 use rand::Rng;
-use votes::{Contest, Tally};
+use votes::{Contest, ContestResult, Error, FlatVote, Tally};
+use votes::{CustomNaming, DefaultNaming, TimestampedNaming};
 use votes::{gen_random_choices, gen_random_contest, gen_random_votes};
 
 
@@ -76,4 +77,604 @@ fn test_io_results() {
     fs::remove_file(&results_file)
         .expect("Failed to remove file after test");
 
-}
\ No newline at end of file
+}
+
+
+#[test]
+fn test_io_votes_csv() {
+
+    // Generate a contest
+    let contest = gen_random_contest(5, gen_random_choices(10));
+
+    // Generate some random votes for this contest
+    let tally = Tally::new(&contest)
+        .with_votes(gen_random_votes(10, &contest));
+
+    // Save these votes to a CSV file
+    let votes_file = format!("votes-{}.csv", contest.id());
+    tally.save_to_csv(&votes_file)
+        .expect("Failed to save votes to CSV");
+
+    // Load these votes and check they match
+    let loaded_tally = Tally::load_from_csv(&votes_file, &contest)
+        .expect("Failed to load votes from CSV");
+
+    assert_eq!(tally, loaded_tally);
+
+    // Remove tmp file
+    fs::remove_file(&votes_file)
+        .expect("Failed to remove file after test");
+
+}
+
+
+#[test]
+fn test_io_votes_multiple_files() {
+
+    // Generate two contests
+    let contest = gen_random_contest(5, gen_random_choices(10));
+    let other_contest = gen_random_contest(2, gen_random_choices(4));
+
+    // Save votes for each contest to its own file
+    let tally = Tally::new(&contest)
+        .with_votes(gen_random_votes(10, &contest));
+    let other_tally = Tally::new(&other_contest)
+        .with_votes(gen_random_votes(5, &other_contest));
+    let votes_file = tally.save_to_file()
+        .expect("Failed to save votes");
+    let other_votes_file = other_tally.save_to_file()
+        .expect("Failed to save votes");
+
+    // Load both files: votes for the other contest are discarded
+    let files = [&votes_file, &other_votes_file];
+    let (loaded_tally, discarded) = Tally::load_from_files_with_discarded(&files, &contest)
+        .expect("Failed to load votes from files");
+
+    assert_eq!(tally, loaded_tally);
+    assert_eq!(5, discarded);
+
+    // Remove tmp files
+    for file in files {
+        fs::remove_file(file)
+            .expect("Failed to remove file after test");
+    }
+
+}
+
+
+#[cfg(feature = "bincode")]
+#[test]
+fn test_io_votes_bin() {
+
+    // Generate a contest
+    let contest = gen_random_contest(5, gen_random_choices(10));
+
+    // Generate some random votes for this contest
+    let tally = Tally::new(&contest)
+        .with_votes(gen_random_votes(10, &contest));
+
+    // Save these votes to a binary file
+    let votes_file = format!("votes-{}.bin", contest.id());
+    tally.save_to_bin(&votes_file)
+        .expect("Failed to save votes to binary file");
+
+    // Load these votes and check they match
+    let loaded_tally = Tally::load_from_bin(&votes_file, &contest)
+        .expect("Failed to load votes from binary file");
+
+    assert_eq!(tally, loaded_tally);
+
+    // Remove tmp file
+    fs::remove_file(&votes_file)
+        .expect("Failed to remove file after test");
+
+}
+
+
+#[cfg(feature = "bincode")]
+#[test]
+fn test_io_votes_bin_corrupted_length() {
+
+    let contest = gen_random_contest(5, gen_random_choices(10));
+    let tally = Tally::new(&contest)
+        .with_votes(gen_random_votes(1, &contest));
+
+    // A length prefix larger than the file is rejected instead of allocated
+    let votes_file = format!("votes-corrupted-{}.bin", contest.id());
+    tally.save_to_bin(&votes_file)
+        .expect("Failed to save votes to binary file");
+    let mut bytes = fs::read(&votes_file)
+        .expect("Failed to read binary file");
+    bytes[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+    fs::write(&votes_file, bytes)
+        .expect("Failed to write binary file");
+    assert!(matches!(Tally::load_from_bin(&votes_file, &contest), Err(Error::IO(_))));
+
+    fs::remove_file(&votes_file)
+        .expect("Failed to remove file after test");
+
+}
+
+
+#[cfg(feature = "bincode")]
+#[test]
+fn test_io_votes_bin_not_migrated() {
+
+    let contest = gen_random_contest(5, gen_random_choices(10));
+    let vote = gen_random_votes(1, &contest).remove(0);
+
+    // Votes encoded before `source` and `schema_version` were added end
+    // right before them, and can't be decoded
+    let mut bytes = vote.to_bytes()
+        .expect("Failed to encode vote");
+    assert_eq!(vote, FlatVote::from_bytes(&bytes).expect("Failed to decode vote"));
+    bytes.truncate(bytes.len() - 1 - 4);
+    assert!(matches!(FlatVote::from_bytes(&bytes), Err(Error::Bincode(_))));
+
+}
+
+
+#[test]
+fn test_io_contest_csv_choices() {
+
+    let csv = "id,name,party,display_order\n\
+        1,Alice,Greens,2\n\
+        2,\"Bob, Jr.\",,1\n\
+        3,Carol,Reds,\n";
+    let contest = Contest::from_csv_choices(csv.as_bytes(), 2)
+        .expect("Failed to read choices");
+    assert_eq!(2, contest.num_winners());
+    assert_eq!(3, contest.num_choices());
+    let bob = contest.choice_by_id(2).unwrap();
+    assert_eq!(("Bob, Jr.", None, 1), (bob.text.as_str(), bob.category.as_deref(), bob.display_order));
+    assert_eq!(Some("Reds"), contest.choice_by_id(3).unwrap().category.as_deref());
+
+    // Round trip
+    let mut out = vec![];
+    contest.choices_to_csv(&mut out)
+        .expect("Failed to write choices");
+    let reloaded = Contest::from_csv_choices(out.as_slice(), 2)
+        .expect("Failed to read choices");
+    assert_eq!(contest.choices(), reloaded.choices());
+
+    // Optional columns may be missing, in any order
+    let contest = Contest::from_csv_choices("name,id\nAlice,1\nBob,2\n".as_bytes(), 1)
+        .expect("Failed to read choices");
+    assert_eq!(vec![1, 2], contest.choices().iter().map(|c| c.id).collect::<Vec<_>>());
+    assert_eq!(None, contest.choices()[0].category);
+
+    assert!(matches!(Contest::from_csv_choices("id,party\n1,Reds\n".as_bytes(), 1), Err(Error::CSV(_))));
+    assert!(matches!(Contest::from_csv_choices("id,name\nx,Alice\n".as_bytes(), 1), Err(Error::CSV(_))));
+    assert!(matches!(Contest::from_csv_choices("id,name\n1,Alice\n1,Bob\n".as_bytes(), 1), Err(Error::DuplicateChoiceId(1))));
+
+}
+
+
+#[test]
+fn test_io_votes_iter() {
+
+    let contest = gen_random_contest(2, gen_random_choices(5));
+    let tally = Tally::new(&contest)
+        .with_votes(gen_random_votes(20, &contest));
+
+    let votes_file = tally.save_to_file()
+        .expect("Failed to save votes");
+
+    // Streamed votes match the loaded ones
+    let streamed: Vec<FlatVote> = Tally::iter_from_file(&votes_file, &contest)
+        .expect("Failed to open votes file")
+        .collect::<Result<_, _>>()
+        .expect("Failed to read votes");
+    assert_eq!(tally.votes(), &streamed);
+
+    // Votes for other contests are skipped
+    let other = gen_random_contest(1, gen_random_choices(3));
+    assert_eq!(0, Tally::iter_from_file(&votes_file, &other).expect("Failed to open votes file").count());
+
+    // A tampered file yields an error and stops
+    let contents = fs::read_to_string(&votes_file)
+        .expect("Failed to read votes file");
+    let lines: Vec<&str> = contents.lines().collect();
+    fs::write(&votes_file, lines[1..].join("\n"))
+        .expect("Failed to write votes file");
+    let results: Vec<_> = Tally::iter_from_file(&votes_file, &contest)
+        .expect("Failed to open votes file")
+        .collect();
+    assert_eq!(20, results.len());
+    assert!(matches!(
+        results.last(),
+        Some(Err(Error::Context{ source, .. })) if matches!(**source, Error::ChecksumMismatch{ .. })
+    ));
+
+    fs::remove_file(&votes_file)
+        .expect("Failed to remove file after test");
+
+    assert!(Tally::iter_from_file(&votes_file, &contest).is_err());
+
+}
+
+
+#[test]
+fn test_io_votes_iter_files() {
+
+    let contest = gen_random_contest(2, gen_random_choices(5));
+    let first = Tally::new(&contest)
+        .with_votes(gen_random_votes(5, &contest));
+    let second = Tally::new(&contest)
+        .with_votes(gen_random_votes(7, &contest));
+
+    let first_file = first.save_to_file_with(&CustomNaming(Box::new(|id| format!("votes-{}-first.json", id))))
+        .expect("Failed to save votes");
+    let second_file = second.save_to_file_with(&CustomNaming(Box::new(|id| format!("votes-{}-second.json", id))))
+        .expect("Failed to save votes");
+
+    // A missing file is reported without stopping the other ones
+    let paths = [first_file.as_str(), "missing-votes.json", second_file.as_str()];
+    let results: Vec<Result<FlatVote, Error>> = Tally::iter_from_files(&paths, &contest)
+        .expect("Failed to read votes")
+        .collect();
+    assert_eq!(13, results.len());
+    assert!(matches!(
+        &results[5],
+        Err(Error::Context{ message, source }) if message.contains("missing-votes.json") && matches!(**source, Error::IO(_))
+    ));
+    let votes: Vec<FlatVote> = results.into_iter().filter_map(Result::ok).collect();
+    assert_eq!(first.votes(), &votes[..5]);
+    assert_eq!(second.votes(), &votes[5..]);
+
+    for file in [first_file, second_file] {
+        fs::remove_file(&file)
+            .expect("Failed to remove file after test");
+    }
+
+}
+
+
+#[test]
+fn test_io_votes_checksum() {
+
+    let contest = gen_random_contest(2, gen_random_choices(5));
+    let tally = Tally::new(&contest)
+        .with_votes(gen_random_votes(10, &contest));
+
+    let votes_file = tally.save_to_file()
+        .expect("Failed to save votes");
+    let contents = fs::read_to_string(&votes_file)
+        .expect("Failed to read votes file");
+    assert!(contents.lines().last().unwrap().starts_with("{\"__checksum\":"));
+
+    // Untouched files load in strict mode
+    let loaded = Tally::load_from_file_strict(&votes_file, &contest)
+        .expect("Failed to load votes from file");
+    assert_eq!(tally, loaded);
+
+    // Dropping a vote breaks the checksum
+    let lines: Vec<&str> = contents.lines().collect();
+    fs::write(&votes_file, lines[1..].join("\n"))
+        .expect("Failed to write votes file");
+    assert!(matches!(
+        Tally::load_from_file(&votes_file, &contest),
+        Err(Error::Context{ source, .. }) if matches!(*source, Error::ChecksumMismatch{ .. })
+    ));
+
+    // Dropping the checksum is only an error in strict mode
+    fs::write(&votes_file, lines[..lines.len() - 1].join("\n"))
+        .expect("Failed to write votes file");
+    assert_eq!(tally, Tally::load_from_file(&votes_file, &contest).expect("Failed to load votes from file"));
+    assert!(matches!(
+        Tally::load_from_file_strict(&votes_file, &contest),
+        Err(Error::Context{ source, .. }) if matches!(*source, Error::MissingChecksum)
+    ));
+
+    fs::remove_file(&votes_file)
+        .expect("Failed to remove file after test");
+
+}
+
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_io_contest_toml() {
+
+    // Generate a contest
+    let num_winners = rand::thread_rng().gen_range(1..5);
+    let contest = gen_random_contest(num_winners, gen_random_choices(15));
+
+    // Save it to a file and load it again
+    let path = format!("contest-{}.toml", contest.id());
+    contest.save_to_toml(&path)
+        .expect("Failed to save contest data to file");
+    let loaded = Contest::load_from_toml(&path)
+        .expect("Failed to load contest data from file");
+
+    // Make sure they match
+    assert!(contest.content_eq(&loaded));
+
+    // Remove tmp file
+    fs::remove_file(&path)
+        .expect("Failed to remove contest file after test");
+
+}
+
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_io_contest_yaml() {
+
+    // Generate a contest
+    let num_winners = rand::thread_rng().gen_range(1..5);
+    let contest = gen_random_contest(num_winners, gen_random_choices(15));
+
+    // Field names match the JSON ones
+    let yaml = contest.to_yaml()
+        .expect("Failed to encode contest");
+    assert!(yaml.contains("num_winners:"));
+    assert!(contest.content_eq(&Contest::from_yaml(&yaml).expect("Failed to decode contest")));
+
+    // Save it to a file and load it again
+    let path = format!("contest-{}.yaml", contest.id());
+    contest.save_to_yaml(&path)
+        .expect("Failed to save contest data to file");
+    let loaded = Contest::load_from_yaml(&path)
+        .expect("Failed to load contest data from file");
+    assert!(contest.content_eq(&loaded));
+
+    // Remove tmp file
+    fs::remove_file(&path)
+        .expect("Failed to remove contest file after test");
+
+}
+
+
+#[cfg(feature = "schemars")]
+#[test]
+fn test_io_schemas() {
+
+    use votes::schema::{contest_result_schema, contest_schema, flat_vote_schema};
+
+    // Every field written to the files is described by the schemas
+    let contest = gen_random_contest(2, gen_random_choices(5));
+    let tally = Tally::new(&contest)
+        .with_votes(gen_random_votes(1, &contest));
+    let files = [
+        (serde_json::to_value(contest_schema()), serde_json::to_value(&contest)),
+        (serde_json::to_value(flat_vote_schema()), serde_json::to_value(&tally.votes()[0])),
+        (serde_json::to_value(contest_result_schema()), serde_json::to_value(tally.result())),
+    ];
+    for (schema, value) in files {
+        let schema = schema.expect("Failed to encode schema");
+        let value = value.expect("Failed to encode value");
+        let properties = schema["properties"].as_object()
+            .expect("Schema should have properties");
+        for key in value.as_object().expect("Value should be an object").keys() {
+            assert!(properties.contains_key(key), "Missing {} in schema", key);
+        }
+    }
+
+}
+
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_io_votes_gz() {
+
+    let contest = gen_random_contest(5, gen_random_choices(10));
+    let tally = Tally::new(&contest)
+        .with_votes(gen_random_votes(100, &contest));
+
+    let votes_file = format!("votes-{}.json.gz", contest.id());
+    tally.save_to_gz(&votes_file)
+        .expect("Failed to save compressed votes");
+
+    let loaded = Tally::load_from_gz(&votes_file, &contest)
+        .expect("Failed to load compressed votes");
+    assert_eq!(tally, loaded);
+
+    // The format is detected from the extension
+    let loaded = Tally::load_from_file_strict(&votes_file, &contest)
+        .expect("Failed to load compressed votes");
+    assert_eq!(tally, loaded);
+
+    fs::remove_file(&votes_file)
+        .expect("Failed to remove file after test");
+
+}
+
+
+#[test]
+fn test_io_file_naming() {
+
+    let contest = gen_random_contest(1, gen_random_choices(5));
+    let tally = Tally::new(&contest)
+        .with_votes(gen_random_votes(10, &contest));
+
+    // Same names as the plain save methods
+    let default_file = tally.save_to_file_with(&DefaultNaming)
+        .expect("Failed to save votes");
+    assert_eq!(format!("votes-{}.json", contest.id()), default_file);
+
+    let timestamped_file = tally.save_to_file_with(&TimestampedNaming)
+        .expect("Failed to save votes");
+    assert!(timestamped_file.starts_with(&format!("votes-{}-", contest.id())));
+    assert!(timestamped_file.ends_with("Z.json"));
+
+    let custom = CustomNaming(Box::new(|id| format!("custom-contest-{}.json", id)));
+    let custom_file = contest.save_to_file_with(&custom)
+        .expect("Failed to save contest");
+    assert_eq!(format!("custom-contest-{}.json", contest.id()), custom_file);
+    assert!(contest.content_eq(&Contest::load_from_file(&custom_file).expect("Failed to load contest")));
+
+    for file in [default_file, timestamped_file, custom_file] {
+        fs::remove_file(&file)
+            .expect("Failed to remove file after test");
+    }
+
+}
+
+
+#[test]
+fn test_io_error_context() {
+
+    let contest = gen_random_contest(1, gen_random_choices(5));
+
+    let err = Contest::load_from_file("missing-contest.json")
+        .expect_err("Loading a missing file should fail");
+    assert!(matches!(err, Error::Context{ source: ref inner, .. } if matches!(**inner, Error::IO(_))));
+    assert!(err.to_string().contains("missing-contest.json"));
+    assert!(std::error::Error::source(&err).is_some());
+
+    let fname = "test_io_error_context.json";
+    fs::write(fname, "not json\n").expect("Failed to write test file");
+    let err = Tally::load_from_file(fname, &contest)
+        .expect_err("Loading an invalid file should fail");
+    assert!(matches!(err, Error::Context{ source: ref inner, .. } if matches!(**inner, Error::JSON(_))));
+    assert!(err.to_string().contains(fname));
+    fs::remove_file(fname)
+        .expect("Failed to remove file after test");
+
+    let err = Error::ContestNotOpen.context("Counting");
+    assert_eq!("Counting: Contest is not open for voting", err.to_string());
+
+}
+
+
+#[test]
+fn test_io_schema_version() {
+
+    let contest = gen_random_contest(1, gen_random_choices(5));
+    assert_eq!(votes::SCHEMA_VERSION, contest.schema_version());
+
+    // Files written before versioning get migrated
+    let mut legacy = serde_json::to_value(&contest).expect("Failed to encode contest");
+    legacy.as_object_mut().unwrap().remove("schema_version");
+    let fname = "test_io_schema_version.json";
+    fs::write(fname, legacy.to_string()).expect("Failed to write test file");
+    let loaded = Contest::load_from_file(fname)
+        .expect("Failed to load legacy contest");
+    assert_eq!(votes::SCHEMA_VERSION, loaded.schema_version());
+    assert!(contest.content_eq(&loaded));
+
+    // Newer versions are rejected
+    legacy["schema_version"] = (votes::SCHEMA_VERSION + 1).into();
+    fs::write(fname, legacy.to_string()).expect("Failed to write test file");
+    let err = Contest::load_from_file(fname)
+        .expect_err("Loading a newer schema version should fail");
+    assert!(matches!(err, Error::Context{ source: ref inner, .. }
+        if matches!(**inner, Error::UnsupportedSchemaVersion(v) if v == votes::SCHEMA_VERSION + 1)));
+
+    // Same for votes
+    let vote = gen_random_votes(1, &contest).remove(0);
+    let mut legacy = serde_json::to_value(&vote).expect("Failed to encode vote");
+    legacy.as_object_mut().unwrap().remove("schema_version");
+    fs::write(fname, format!("{}\n", legacy)).expect("Failed to write test file");
+    let tally = Tally::load_from_file(fname, &contest)
+        .expect("Failed to load legacy votes");
+    assert_eq!(&[vote], tally.votes());
+
+    fs::remove_file(fname)
+        .expect("Failed to remove file after test");
+
+}
+
+
+#[test]
+fn test_io_checkpoint() {
+
+    let contest = gen_random_contest(3, gen_random_choices(10));
+    let votes = gen_random_votes(80, &contest);
+    let fname = format!("checkpoint-{}.json", contest.id());
+
+    // Save the count of the first votes
+    let tally = Tally::new(&contest).with_votes(votes[..50].to_vec());
+    assert_eq!(50, tally.checkpoint(&fname).expect("Failed to save checkpoint"));
+
+    // Carry on with the rest
+    let mut partial = Tally::restore_from_checkpoint(&fname, &contest)
+        .expect("Failed to restore checkpoint");
+    assert_eq!(50, partial.processed());
+    for vote in &votes[partial.processed()..] {
+        partial.feed(vote);
+    }
+    assert_eq!(80, partial.processed());
+    assert_eq!(Tally::new(&contest).with_votes(votes).result(), partial.snapshot());
+
+    // Checkpoints only restore for their own contest
+    let other = gen_random_contest(1, gen_random_choices(3));
+    let err = Tally::restore_from_checkpoint(&fname, &other)
+        .expect_err("Restoring a checkpoint for another contest should fail");
+    assert!(matches!(err, Error::Context{ source: ref inner, .. }
+        if matches!(**inner, Error::ContestMismatch{ .. })));
+
+    fs::remove_file(&fname)
+        .expect("Failed to remove file after test");
+
+}
+
+
+#[test]
+fn test_io_votes_with_capacity() {
+
+    let contest = gen_random_contest(5, gen_random_choices(10));
+    let tally = Tally::new(&contest)
+        .with_votes(gen_random_votes(10, &contest));
+    let votes_file = tally.save_to_file()
+        .expect("Failed to save votes");
+
+    // Same votes with room for more, whether the estimate is short or not
+    for capacity in [0, 10, 100] {
+        let loaded_tally = Tally::load_from_file_with_capacity(&votes_file, &contest, capacity)
+            .expect("Failed to load votes from file");
+        assert_eq!(tally, loaded_tally);
+    }
+
+    fs::remove_file(&votes_file)
+        .expect("Failed to remove file after test");
+
+}
+
+
+#[test]
+fn test_io_json() {
+
+    let contest = gen_random_contest(5, gen_random_choices(10));
+    let result = Tally::new(&contest)
+        .with_votes(gen_random_votes(10, &contest))
+        .result();
+
+    // Contests and results survive a round trip through a JSON string
+    let json = contest.to_json()
+        .expect("Failed to encode contest");
+    let decoded = Contest::from_json(&json)
+        .expect("Failed to decode contest");
+    assert!(contest.content_eq(&decoded));
+
+    let json = result.to_json()
+        .expect("Failed to encode results");
+    let decoded = ContestResult::from_json(&json)
+        .expect("Failed to decode results");
+    assert_eq!(result, decoded);
+    assert!(result.contest.content_eq(&decoded.contest));
+
+}
+
+
+#[test]
+fn test_io_load_results() {
+
+    let contest = gen_random_contest(5, gen_random_choices(10));
+    let result = Tally::new(&contest)
+        .with_votes(gen_random_votes(10, &contest))
+        .result();
+    let results_file = result.save_to_file()
+        .expect("Failed to save contest results to disk");
+
+    // Results survive a round trip through the saved file
+    let loaded = ContestResult::load_from_file(&results_file)
+        .expect("Failed to load results from file");
+    assert_eq!(result, loaded);
+    assert!(result.contest.content_eq(&loaded.contest));
+
+    fs::remove_file(&results_file)
+        .expect("Failed to remove file after test");
+
+}
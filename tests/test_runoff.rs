@@ -0,0 +1,143 @@
+// test_runoff.rs
+
+use votes::{Contest, ContestBuilder, ContestChoice, DecodedContestVote, DecodedVoteChoice, FlatVote, Tally};
+use votes::RunoffTally;
+
+
+/// `count` votes for a single choice
+fn votes_for(contest: &Contest, choice: &ContestChoice, count: usize) -> Vec<FlatVote> {
+    (0..count)
+        .map(|_| DecodedContestVote::new(contest, vec![DecodedVoteChoice::new(choice.clone())]).into())
+        .collect()
+}
+
+
+#[test]
+fn runoff_second_round() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Alice"),
+        ContestChoice::new(200, "Bob"),
+        ContestChoice::new(300, "Carol"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+    let runoff_contest = ContestBuilder::new(1, &choices[..2])
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+
+    // Alice leads without a majority
+    let mut round_one = votes_for(&contest, &choices[0], 4);
+    round_one.extend(votes_for(&contest, &choices[1], 3));
+    round_one.extend(votes_for(&contest, &choices[2], 2));
+
+    // Carol's voters back Bob in the second round
+    let mut round_two = votes_for(&runoff_contest, &choices[0], 4);
+    round_two.extend(votes_for(&runoff_contest, &choices[1], 5));
+
+    let runoff = RunoffTally::new(
+        Tally::new(&contest).with_votes(round_one),
+        Tally::new(&runoff_contest).with_votes(round_two),
+    );
+    assert!(runoff.needed_round_two());
+
+    let result = runoff.result();
+    assert_eq!(vec![choices[0].clone(), choices[1].clone()], result.finalists);
+    assert!(result.round_two.is_some());
+    assert_eq!(Some(&choices[1]), result.winner());
+
+}
+
+
+#[test]
+fn runoff_outright_win() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Alice"),
+        ContestChoice::new(200, "Bob"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+
+    let mut votes = votes_for(&contest, &choices[0], 6);
+    votes.extend(votes_for(&contest, &choices[1], 4));
+
+    let runoff = RunoffTally::new(Tally::new(&contest).with_votes(votes), Tally::new(&contest));
+    assert!(!runoff.needed_round_two());
+
+    let result = runoff.result();
+    assert!(result.finalists.is_empty());
+    assert_eq!(None, result.round_two);
+    assert_eq!(Some(&choices[0]), result.winner());
+
+}
+
+
+#[test]
+fn runoff_without_votes() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Alice"),
+        ContestChoice::new(200, "Bob"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+
+    // Nobody to send to a second round
+    let runoff = RunoffTally::new(
+        Tally::new(&contest),
+        Tally::new(&contest),
+    );
+    assert!(!runoff.needed_round_two());
+
+    let result = runoff.result();
+    assert!(result.finalists.is_empty());
+    assert_eq!(None, result.round_two);
+    assert_eq!(None, result.winner());
+
+}
+
+
+#[test]
+fn runoff_only_counts_finalists() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Alice"),
+        ContestChoice::new(200, "Bob"),
+        ContestChoice::new(300, "Carol"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+
+    // Bob and Carol tie for second place, Bob has the lowest ID
+    let mut round_one = votes_for(&contest, &choices[0], 4);
+    round_one.extend(votes_for(&contest, &choices[2], 3));
+    round_one.extend(votes_for(&contest, &choices[1], 3));
+
+    // Carol was eliminated, her second round votes are left out
+    let mut round_two = votes_for(&contest, &choices[0], 4);
+    round_two.extend(votes_for(&contest, &choices[1], 3));
+    round_two.extend(votes_for(&contest, &choices[2], 5));
+
+    let runoff = RunoffTally::new(
+        Tally::new(&contest).with_votes(round_one),
+        Tally::new(&contest).with_votes(round_two),
+    );
+    let result = runoff.result();
+    assert_eq!(vec![choices[0].clone(), choices[1].clone()], result.finalists);
+
+    let round_two = result.round_two.as_ref().expect("Failed to hold a second round");
+    assert_eq!(7, round_two.total_valid_votes);
+    assert_eq!(vec![choices[2].clone()], round_two.disqualified_choices);
+    assert_eq!(Some(&choices[0]), result.winner());
+
+}
@@ -0,0 +1,117 @@
+// test_stv.rs
+
+use votes::{ContestBuilder, ContestChoice, DecodedContestVote, DecodedVoteChoice, FlatVote, Tally};
+use votes::StvTally;
+
+
+/// Builds a ranked vote where every choice gets its preference rank
+fn ranked_vote(contest: &votes::Contest, choices: &[&ContestChoice]) -> FlatVote {
+    let ranked = choices.iter().enumerate().map(|(i, c)| {
+        let mut choice = DecodedVoteChoice::new((*c).clone());
+        choice.selected = i as u64 + 1;
+        choice
+    }).collect();
+    DecodedContestVote::new(contest, ranked).into()
+}
+
+
+#[test]
+fn stv_surplus_transfer() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Alice"),
+        ContestChoice::new(200, "Bob"),
+        ContestChoice::new(300, "Carol"),
+    ];
+    let contest = ContestBuilder::new(2, &choices)
+        .max_choices(3)
+        .min_choices(1)
+        .build();
+
+    let (alice, bob, carol) = (&choices[0], &choices[1], &choices[2]);
+    let mut votes = vec![];
+    votes.extend((0..6).map(|_| ranked_vote(&contest, &[alice, bob])));
+    votes.extend((0..2).map(|_| ranked_vote(&contest, &[bob])));
+    votes.extend((0..3).map(|_| ranked_vote(&contest, &[carol])));
+
+    let stv = StvTally::new(Tally::new(&contest).with_votes(votes));
+    let result = stv.result();
+
+    // floor(11 / 3) + 1
+    assert_eq!(4, result.quota);
+
+    // Alice is elected straight away with a surplus of 2 votes...
+    assert_eq!(vec![100], result.rounds[0].elected);
+    assert_eq!(6.0, result.rounds[0].totals[&100]);
+
+    // ... which transfers to Bob, who then reaches the quota
+    assert_eq!(vec![200], result.rounds[1].elected);
+    assert!((result.rounds[1].totals[&200] - 4.0).abs() < 1e-9);
+
+    let winners: Vec<i64> = result.winners.iter().map(|c| c.id).collect();
+    assert_eq!(vec![100, 200], winners);
+
+}
+
+
+#[test]
+fn stv_elimination() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Alice"),
+        ContestChoice::new(200, "Bob"),
+        ContestChoice::new(300, "Carol"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(3)
+        .min_choices(1)
+        .build();
+
+    let (alice, bob, carol) = (&choices[0], &choices[1], &choices[2]);
+    let mut votes = vec![];
+    votes.extend((0..4).map(|_| ranked_vote(&contest, &[alice])));
+    votes.extend((0..3).map(|_| ranked_vote(&contest, &[bob, alice])));
+    votes.extend((0..2).map(|_| ranked_vote(&contest, &[carol, bob])));
+
+    let result = StvTally::new(Tally::new(&contest).with_votes(votes)).result();
+
+    // floor(9 / 2) + 1
+    assert_eq!(5, result.quota);
+
+    // Nobody meets the quota, so Carol is eliminated and her votes go to Bob
+    assert_eq!(Some(300), result.rounds[0].eliminated);
+    assert!(result.rounds[0].elected.is_empty());
+    assert_eq!(vec![200], result.rounds[1].elected);
+    assert_eq!(200, result.winners[0].id);
+
+}
+
+
+#[test]
+fn stv_exhausted_ballots() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Alice"),
+        ContestChoice::new(200, "Bob"),
+        ContestChoice::new(300, "Carol"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(3)
+        .min_choices(1)
+        .build();
+
+    let (alice, bob, carol) = (&choices[0], &choices[1], &choices[2]);
+    let mut votes = vec![];
+    votes.extend((0..4).map(|_| ranked_vote(&contest, &[alice])));
+    votes.extend((0..3).map(|_| ranked_vote(&contest, &[bob])));
+    votes.extend((0..2).map(|_| ranked_vote(&contest, &[carol])));
+
+    let result = StvTally::new(Tally::new(&contest).with_votes(votes)).result();
+
+    // Carol and then Bob are eliminated with no further preferences
+    assert_eq!(Some(300), result.rounds[0].eliminated);
+    assert_eq!(Some(200), result.rounds[1].eliminated);
+    assert_eq!(100, result.winners[0].id);
+    assert_eq!(5, result.exhausted_ballots);
+
+}
@@ -0,0 +1,98 @@
+// test_tracing.rs
+
+#![cfg(feature = "tracing")]
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use votes::{Contest, Tally};
+use votes::{gen_random_choices, gen_random_contest, gen_random_votes};
+
+
+/// A span name with the names of the fields recorded on it
+type SpanFields = (&'static str, BTreeSet<&'static str>);
+
+/// Every span with its fields, in creation order
+#[derive(Clone, Default)]
+struct CapturedSpans(Arc<Mutex<Vec<SpanFields>>>);
+
+/// Index of a span in [CapturedSpans], kept in the span extensions
+struct SpanIndex(usize);
+
+/// Collects the names of the fields that get a value
+struct FieldNames<'a>(&'a mut BTreeSet<&'static str>);
+
+impl Visit for FieldNames<'_> {
+    fn record_debug(&mut self, field: &Field, _value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name());
+    }
+}
+
+impl<S> Layer<S> for CapturedSpans
+where S: Subscriber + for<'a> LookupSpan<'a> {
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut spans = self.0.lock().expect("Failed to lock captured spans");
+        let mut fields = BTreeSet::new();
+        attrs.record(&mut FieldNames(&mut fields));
+        spans.push((attrs.metadata().name(), fields));
+        let span = ctx.span(id).expect("Failed to find new span");
+        span.extensions_mut().insert(SpanIndex(spans.len() - 1));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("Failed to find recorded span");
+        let extensions = span.extensions();
+        let index = extensions.get::<SpanIndex>().expect("Failed to find span index");
+        let mut spans = self.0.lock().expect("Failed to lock captured spans");
+        values.record(&mut FieldNames(&mut spans[index.0].1));
+    }
+
+}
+
+
+#[test]
+fn spans_carry_contest_votes_and_elapsed_time() {
+
+    let captured = CapturedSpans::default();
+    let subscriber = tracing_subscriber::registry().with(captured.clone());
+
+    let contest = gen_random_contest(2, gen_random_choices(5));
+    let tally = Tally::new(&contest)
+        .with_votes(gen_random_votes(20, &contest));
+    let (contest_file, votes_file) = tracing::subscriber::with_default(subscriber, || {
+        let contest_file = contest.save_to_file()
+            .expect("Failed to save contest");
+        Contest::load_from_file(&contest_file)
+            .expect("Failed to load contest");
+        let votes_file = tally.save_to_file()
+            .expect("Failed to save votes");
+        Tally::load_from_file(&votes_file, &contest)
+            .expect("Failed to load votes");
+        tally.result();
+        (contest_file, votes_file)
+    });
+
+    // Every instrumented call gets its span, with all of its fields filled in
+    let spans = captured.0.lock().expect("Failed to lock captured spans");
+    let names: Vec<&str> = spans.iter().map(|(name, _)| *name).collect();
+    assert_eq!(vec!["save_to_file", "load_from_file", "save_to_file", "load_from_file", "result"], names);
+    for (name, fields) in spans.iter() {
+        assert!(fields.contains("contest_id"), "No contest ID in `{}`", name);
+        assert!(fields.contains("elapsed"), "No elapsed time in `{}`", name);
+    }
+    for (_, fields) in &spans[2..] {
+        assert!(fields.contains("num_votes"));
+    }
+
+    fs::remove_file(&contest_file)
+        .expect("Failed to remove file after test");
+    fs::remove_file(&votes_file)
+        .expect("Failed to remove file after test");
+
+}
@@ -1,10 +1,15 @@
 // test_votes.rs
 
-use votes::{ContestBuilder, ContestChoice, DecodedContestVote, DecodedVoteChoice, FlatVote, Tally};
-use votes::{gen_random_choices, gen_random_contest, gen_random_votes};
+use std::collections::{HashMap, HashSet};
+use votes::{Contest, ContestBuilder, ContestChoice, ContestPhase, ContestResult, DecodedContestVote, DecodedVoteChoice, Error, FlatVote};
+use votes::{ContestId, InvalidReason, ValidationError, VoteValidity};
+use votes::{FlatVoteBuilder, IncrementalTally, PluralityAtLarge, Tally, TallyMethod, TallyMode};
+use votes::{gen_random_choices, gen_random_choices_from, gen_random_contest, gen_random_contest_builder, gen_random_votes};
+use votes::{gen_random_votes_biased, gen_random_votes_with_invalid_ratio, gen_random_votes_with_sources};
 
 
 #[test]
+#[allow(clippy::redundant_closure)]
 fn simple_contest_test() {
 
     // Generate 3 choices
@@ -81,6 +86,7 @@ fn simple_contest_test() {
 
 
 #[test]
+#[allow(clippy::redundant_closure)]
 fn simple_contest_test_with_ties () {
 
     // Generate 4 choices
@@ -142,6 +148,7 @@ fn simple_contest_test_with_ties () {
 
 
 #[test]
+#[allow(clippy::redundant_closure)]
 fn contest_with_invalid_votes () {
 
     // Generate 4 choices
@@ -275,3 +282,2277 @@ fn test_with_random_generator() {
     }
 
 }
+
+
+#[test]
+fn validate_contest_builder() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Jimi Hendrix"),
+        ContestChoice::new(200, "Stevie Ray Vaughan"),
+    ];
+
+    // Invariants are checked without consuming the builder
+    let builder = ContestBuilder::new(3, &choices)
+        .min_choices(2)
+        .max_choices(1);
+    assert_eq!(
+        vec![ValidationError::MinExceedsMax, ValidationError::NumWinnersExceedsChoices],
+        builder.validate()
+    );
+    let builder = builder.max_choices(2);
+    assert_eq!(vec![ValidationError::NumWinnersExceedsChoices], builder.validate());
+
+    let builder = ContestBuilder::new(0, &[]);
+    assert_eq!(
+        vec![ValidationError::NumWinnersZero, ValidationError::EmptyChoices],
+        builder.validate()
+    );
+
+    let builder = ContestBuilder::new(1, &choices)
+        .add_choice(ContestChoice::new(100, "Eddie Van Halen"))
+        .max_choices(1);
+    assert_eq!(vec![ValidationError::DuplicateChoiceId(100)], builder.validate());
+    assert!(ContestBuilder::new(1, &choices).max_choices(1).validate().is_empty());
+
+}
+
+
+#[test]
+fn contest_with_duplicate_choice_ids() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Jimi Hendrix"),
+        ContestChoice::new(200, "Stevie Ray Vaughan"),
+        ContestChoice::new(100, "Eddie Van Halen"),
+    ];
+
+    let result = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build_validated();
+
+    assert!(matches!(result, Err(Error::DuplicateChoiceId(100))));
+
+}
+
+
+#[test]
+fn contest_result_to_csv() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Simon, Paul"),
+        ContestChoice::new(200, "Art Garfunkel"),
+        ContestChoice::new(300, "Carole King"),
+    ];
+
+    // Create a contest with 1 winner
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+
+    let decoded_votes = vec![
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[1].clone())]),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[0].clone())]),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[0].clone())]),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[0].clone())]),
+    ];
+
+    let flat_votes = decoded_votes.into_iter().map(FlatVote::from).collect();
+    let result = Tally::new(&contest).with_votes(flat_votes).result();
+
+    let expected = "choice_id,choice_text,total_count,percentage,winner_position\n\
+        100,\"Simon, Paul\",3,75.00,1\n\
+        200,Art Garfunkel,1,25.00,0\n";
+    assert_eq!(expected, result.to_csv());
+
+}
+
+
+#[test]
+fn contest_result_tables() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Simon | Garfunkel"),
+        ContestChoice::new(200, "Sonny & Cher"),
+        ContestChoice::new(300, "Carole King"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+
+    let decoded_votes = vec![
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[0].clone())]),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[0].clone())]),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[1].clone())]),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[2].clone())]),
+    ];
+    let flat_votes = decoded_votes.into_iter().map(FlatVote::from).collect();
+    let result = Tally::new(&contest).with_votes(flat_votes).result();
+
+    // Ties share the same rank
+    let expected = "| Rank | Candidate | Votes | % |\n\
+        |---:|---|---:|---:|\n\
+        | 1 | Simon \\| Garfunkel | 2 | 50.00 |\n\
+        | 2 | Sonny & Cher | 1 | 25.00 |\n\
+        | 2 | Carole King | 1 | 25.00 |\n";
+    assert_eq!(expected, result.to_markdown_table());
+
+    let expected = "<table>\n  <thead>\n    \
+        <tr><th>Rank</th><th>Candidate</th><th>Votes</th><th>%</th></tr>\n  </thead>\n  <tbody>\n    \
+        <tr class=\"winner\"><td>1</td><td>Simon | Garfunkel</td><td>2</td><td>50.00</td></tr>\n    \
+        <tr><td>2</td><td>Sonny &amp; Cher</td><td>1</td><td>25.00</td></tr>\n    \
+        <tr><td>2</td><td>Carole King</td><td>1</td><td>25.00</td></tr>\n  \
+        </tbody>\n</table>\n";
+    assert_eq!(expected, result.to_html_table());
+
+}
+
+
+#[test]
+fn contest_result_audit_summary() {
+
+    let choices = vec![
+        ContestChoice::new(300, "Aretha Franklin"),
+        ContestChoice::new(100, "Otis Redding"),
+        ContestChoice::new(200, "James Brown"),
+    ];
+    let contest = ContestBuilder::new(2, &choices)
+        .custom_id(7)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+
+    let mut votes: Vec<FlatVote> = [0, 0, 0, 1, 2, 2, 2]
+        .iter()
+        .map(|i| DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[*i].clone())]).into())
+        .collect();
+    votes.push(FlatVote::blank(&contest));
+    let result = Tally::new(&contest).with_votes(votes.clone()).result();
+
+    // Tied winners are ordered by ID
+    let summary = result.audit_summary();
+    assert_eq!(ContestId(7), summary.contest_id);
+    assert_eq!((7, 1), (summary.total_valid, summary.total_invalid));
+    assert_eq!(vec![(100, 1), (200, 3), (300, 3)], summary.choice_counts.into_iter().collect::<Vec<_>>());
+    assert_eq!(vec![200, 300], summary.winners);
+
+    // Independent counts of the same votes agree, in any order
+    votes.reverse();
+    let recount = Tally::new(&contest).with_votes(votes.clone()).result();
+    assert_eq!(result.audit_summary().checksum(), recount.audit_summary().checksum());
+    assert_eq!(64, recount.audit_summary().checksum().len());
+
+    votes.pop();
+    let different = Tally::new(&contest).with_votes(votes).result();
+    assert_ne!(result.audit_summary().checksum(), different.audit_summary().checksum());
+
+}
+
+
+#[test]
+fn contest_result_statistics() {
+
+    // Results with the given vote counts, using weighted votes
+    let result_with_counts = |counts: &[u64]| -> ContestResult {
+        let choices = gen_random_choices(counts.len());
+        let contest = ContestBuilder::new(1, &choices)
+            .max_choices(1)
+            .min_choices(1)
+            .build();
+        let votes = choices.iter().zip(counts)
+            .map(|(choice, count)| {
+                let choice = DecodedVoteChoice::with_weight(choice.clone(), *count);
+                DecodedContestVote::new(&contest, vec![choice]).into()
+            })
+            .collect();
+        Tally::new(&contest).with_votes(votes).result()
+    };
+
+    let stats = result_with_counts(&[40, 10, 30, 20]).statistics();
+    assert_eq!(25.0, stats.mean);
+    assert_eq!(25.0, stats.median);
+    assert!((stats.std_dev - 125f64.sqrt()).abs() < 1e-9);
+    assert!((stats.gini - 0.25).abs() < 1e-9);
+
+    let stats = result_with_counts(&[1, 7, 1]).statistics();
+    assert_eq!(3.0, stats.mean);
+    assert_eq!(1.0, stats.median);
+    assert!((stats.std_dev - 8f64.sqrt()).abs() < 1e-9);
+    assert!((stats.gini - 4.0 / 9.0).abs() < 1e-9);
+
+    // Perfect equality
+    let stats = result_with_counts(&[5, 5, 5]).statistics();
+    assert_eq!(0.0, stats.std_dev);
+    assert_eq!(0.0, stats.gini);
+
+    let stats = result_with_counts(&[]).statistics();
+    assert_eq!((0.0, 0.0, 0.0, 0.0), (stats.mean, stats.median, stats.std_dev, stats.gini));
+
+}
+
+
+#[test]
+fn contest_result_entropy() {
+
+    let choices = gen_random_choices(4);
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+    let result_with_counts = |counts: &[u64]| -> ContestResult {
+        let votes = choices.iter().zip(counts)
+            .filter(|(_, count)| **count > 0)
+            .map(|(choice, count)| {
+                let choice = DecodedVoteChoice::with_weight(choice.clone(), *count);
+                DecodedContestVote::new(&contest, vec![choice]).into()
+            })
+            .collect();
+        Tally::new(&contest).with_votes(votes).result()
+    };
+
+    // Even distribution has maximum entropy
+    let result = result_with_counts(&[5, 5, 5, 5]);
+    assert!((result.entropy - 2.0).abs() < 1e-9);
+    assert!((result.normalized_entropy() - 1.0).abs() < 1e-9);
+
+    let result = result_with_counts(&[2, 1, 1, 0]);
+    assert!((result.entropy - 1.5).abs() < 1e-9);
+    assert!((result.normalized_entropy() - 0.75).abs() < 1e-9);
+
+    // Winner takes all
+    let result = result_with_counts(&[7, 0, 0, 0]);
+    assert_eq!(0.0, result.entropy);
+    assert_eq!(0.0, result.normalized_entropy());
+
+    let result = result_with_counts(&[0, 0, 0, 0]);
+    assert_eq!(0.0, result.entropy);
+
+    // Shares are taken over the weighted selections, not the valid votes
+    let votes = vec![
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::with_weight(choices[0].clone(), 3)]).into(),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[1].clone())]).into(),
+    ];
+    let result = Tally::new(&contest).with_votes(votes).result();
+    assert_eq!(2, result.total_valid_votes);
+    let expected = -0.75 * 0.75f64.log2() - 0.25 * 0.25f64.log2();
+    assert!(result.entropy >= 0.0);
+    assert!((result.entropy - expected).abs() < 1e-9);
+
+}
+
+
+#[test]
+fn filter_and_partition_votes() {
+
+    // Generate a contest
+    let contest = gen_random_contest(3, gen_random_choices(10));
+
+    // Tag half of the votes with a voter ID
+    let votes = gen_random_votes(100, &contest).into_iter().enumerate()
+        .map(|(i, v)| if i % 2 == 0 { v.with_voter_id(&format!("voter-{}", i)) } else { v })
+        .collect();
+    let tally = Tally::new(&contest).with_votes(votes);
+
+    let identified = tally.filter_votes(|v| v.voter_id().is_some());
+    assert_eq!(50, identified.votes().len());
+    assert_eq!(contest.id(), identified.contest().id());
+
+    let (identified, anonymous) = tally.partition_votes(|v| v.voter_id().is_some());
+    assert_eq!(50, identified.votes().len());
+    assert_eq!(50, anonymous.votes().len());
+    assert!(anonymous.votes().iter().all(|v| v.voter_id().is_none()));
+
+}
+
+
+#[test]
+fn contest_with_quorum() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Yes"),
+        ContestChoice::new(200, "No"),
+    ];
+
+    // Create a contest that requires at least 5 valid votes
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .quorum(5)
+        .build();
+
+    let votes: Vec<FlatVote> = (0..4)
+        .map(|_| DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[0].clone())]).into())
+        .collect();
+    let mut tally = Tally::new(&contest).with_votes(votes);
+
+    // Quorum not met: raw winners are kept but there are no effective winners
+    let result = tally.result();
+    assert_eq!(Some(false), result.quorum_met);
+    assert_eq!(1, result.winners.len());
+    assert!(result.effective_winners().is_empty());
+
+    // One more vote meets the quorum
+    tally.add_vote(DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[1].clone())]).into());
+    let result = tally.result();
+    assert_eq!(Some(true), result.quorum_met);
+    assert_eq!(100, result.effective_winners()[0].id);
+
+}
+
+
+#[test]
+fn add_votes_in_batches() {
+
+    let contest = gen_random_contest(2, gen_random_choices(5));
+    let other_contest = gen_random_contest(1, gen_random_choices(3));
+
+    let mut tally = Tally::new(&contest);
+    tally.add_votes(gen_random_votes(10, &contest));
+    assert_eq!(10, tally.votes().len());
+
+    // Votes for other contests are left out
+    let mixed = gen_random_votes(5, &contest).into_iter()
+        .chain(gen_random_votes(5, &other_contest));
+    tally.add_votes(mixed);
+    assert_eq!(15, tally.votes().len());
+
+    // Any iterator will do
+    let extra = gen_random_votes(3, &contest);
+    tally.add_votes(extra.iter().cloned());
+    assert_eq!(&extra[..], &tally.votes()[15..]);
+
+}
+
+
+#[test]
+fn incremental_result_matches_full_count() {
+
+    let contest = gen_random_contest(3, gen_random_choices(10));
+    let votes = gen_random_votes_with_invalid_ratio(1_000, &contest, 0.1);
+    let (first, rest) = votes.split_at(400);
+
+    let tally = Tally::new(&contest).with_votes(first.to_vec());
+    let full = Tally::new(&contest).with_votes(votes.clone());
+    assert_eq!(tally.result(), tally.result_incremental(std::iter::empty()));
+    assert_eq!(full.result(), tally.result_incremental(rest.iter().cloned()));
+
+    // Snapshots can be taken at any point
+    let mut incremental = IncrementalTally::new(&contest);
+    for vote in first {
+        incremental.feed(vote);
+    }
+    assert_eq!(tally.result(), incremental.snapshot());
+    for vote in rest {
+        incremental.feed(vote);
+    }
+    let other_contest = gen_random_contest(1, gen_random_choices(3));
+    incremental.feed(&gen_random_votes(1, &other_contest)[0]);
+    assert_eq!(full.result(), incremental.snapshot());
+
+}
+
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_result_matches_sequential() {
+
+    let contest = gen_random_contest(3, gen_random_choices(10));
+    let tally = Tally::new(&contest)
+        .with_votes(gen_random_votes(50_000, &contest));
+
+    let sequential = tally.result();
+    let parallel = tally.result_parallel();
+
+    assert_eq!(sequential.total_valid_votes, parallel.total_valid_votes);
+    assert_eq!(sequential.total_invalid_votes, parallel.total_invalid_votes);
+    for r in &sequential.results {
+        let p = parallel.results.iter().find(|p| p.contest_choice.id == r.contest_choice.id)
+            .expect("Failed to find choice among parallel results");
+        assert_eq!(r.total_count, p.total_count);
+        assert_eq!(r.winner_position, p.winner_position);
+    }
+
+}
+
+
+#[test]
+fn contest_with_write_ins() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Bob Dylan"),
+        ContestChoice::new(200, "Joan Baez"),
+    ];
+
+    // Create a contest that allows write-ins
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .allows_write_ins(true)
+        .build();
+
+    let decoded_votes = vec![
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::write_in("Woody Guthrie")]),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::write_in("Pete Seeger")]),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::write_in("Leonard Cohen")]),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[0].clone())]),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[0].clone())]),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[1].clone())]),
+    ];
+    let flat_votes = decoded_votes.into_iter().map(FlatVote::from).collect();
+    let result = Tally::new(&contest).with_votes(flat_votes).result();
+
+    // Write-ins are aggregated but never win
+    assert_eq!(6, result.total_valid_votes);
+    assert_eq!(Some(3), result.write_in_votes());
+    assert_eq!(100, result.winners[0].id);
+    let write_ins = result.results.iter().find(|r| r.contest_choice.is_write_in())
+        .expect("Failed to find write-ins among contest results");
+    assert_eq!(0, write_ins.winner_position);
+
+    // Write-ins are invalid when the contest doesn't allow them
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+    let vote = DecodedContestVote::new(&contest, vec![DecodedVoteChoice::write_in("Woody Guthrie")]);
+    assert_eq!(Some(InvalidReason::ChoiceNotInContest(votes::WRITE_IN_ID)), vote.reason());
+    let result = Tally::new(&contest).with_votes(vec![vote.into()]).result();
+    assert_eq!(None, result.write_in_votes());
+
+}
+
+
+#[test]
+fn contest_choices_display_order() {
+
+    let choices = vec![
+        ContestChoice::new(300, "Nina Simone").with_display_order(1),
+        ContestChoice::new(200, "Ella Fitzgerald").with_display_order(2),
+        ContestChoice::new(100, "Billie Holiday").with_display_order(1),
+        ContestChoice::new(400, "Sarah Vaughan"),
+    ];
+    let contest = ContestBuilder::new(1, &choices).build();
+
+    let ordered: Vec<i64> = contest.choices_ordered().iter().map(|c| c.id).collect();
+    assert_eq!(vec![400, 100, 300, 200], ordered);
+
+}
+
+
+#[test]
+fn contest_choices_shuffled() {
+
+    let contest = gen_random_contest(1, gen_random_choices(20));
+    let ids = |choices: Vec<&ContestChoice>| -> Vec<i64> { choices.iter().map(|c| c.id).collect() };
+
+    // Same seed, same order
+    let shuffled = ids(contest.shuffled_choices_with_seed(42));
+    assert_eq!(shuffled, ids(contest.shuffled_choices_with_seed(42)));
+    assert_ne!(shuffled, ids(contest.shuffled_choices_with_seed(43)));
+
+    // Still the same choices, and the definition order is kept
+    let mut sorted = shuffled.clone();
+    sorted.sort_unstable();
+    let mut original = ids(contest.choices().iter().collect());
+    assert_ne!(original, shuffled);
+    original.sort_unstable();
+    assert_eq!(original, sorted);
+    assert_eq!(20, contest.shuffled_choices().len());
+
+}
+
+
+#[test]
+fn display_contest_and_result() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Miles Davis"),
+        ContestChoice::new(200, "John Coltrane"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .custom_id(42)
+        .description("Jazz legends")
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+
+    assert_eq!("Contest #42: Jazz legends (2 choices, 1 winners)", contest.to_string());
+    assert_eq!("[100] Miles Davis", choices[0].to_string());
+
+    let vote = FlatVote::from(DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[0].clone())]));
+    assert_eq!("Vote for contest #42 (1 choices)", vote.to_string());
+
+    let result = Tally::new(&contest).with_votes(vec![vote]).result();
+    let expected = "Contest #42: Jazz legends (2 choices, 1 winners)\n\
+        Valid votes: 1\n\
+        Invalid votes: 0\n\
+        Winners:\n  \
+        1. [100] Miles Davis (1 votes)";
+    assert_eq!(expected, result.to_string());
+
+}
+
+
+#[test]
+fn contest_result_sorted_views() {
+
+    let choices = vec![
+        ContestChoice::new(300, "Aretha Franklin"),
+        ContestChoice::new(100, "Otis Redding"),
+        ContestChoice::new(200, "James Brown"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+
+    let decoded_votes = vec![
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[1].clone())]),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[2].clone())]),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[2].clone())]),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[0].clone())]),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[0].clone())]),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[0].clone())]),
+    ];
+    let flat_votes = decoded_votes.into_iter().map(FlatVote::from).collect();
+    let result = Tally::new(&contest).with_votes(flat_votes).result();
+
+    let ids = |results: Vec<&votes::ContestChoiceResult>| -> Vec<i64> {
+        results.iter().map(|r| r.contest_choice.id).collect()
+    };
+    assert_eq!(vec![300, 200, 100], ids(result.results_by_count()));
+    assert_eq!(vec![300, 200, 100], ids(result.results_by_name()));
+    assert_eq!(vec![100, 200, 300], ids(result.results_by_id()));
+
+    // Default order is by descending count
+    assert_eq!(vec![300, 200, 100], ids(result.results.iter().collect()));
+
+}
+
+
+#[test]
+fn invalidate_flat_votes() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Yes"),
+        ContestChoice::new(200, "No"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+
+    let mut vote = FlatVote::from(DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[0].clone())]));
+    assert!(!vote.is_invalid());
+    vote.invalidate();
+    assert!(vote.is_invalid());
+
+    // Abstentions are counted as invalid votes
+    let blank = FlatVote::blank(&contest);
+    assert!(blank.is_invalid());
+    let result = Tally::new(&contest).with_votes(vec![vote, blank]).result();
+    assert_eq!(0, result.total_valid_votes);
+    assert_eq!(2, result.total_invalid_votes);
+
+}
+
+
+#[test]
+fn inspect_flat_vote_choices() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Yes"),
+        ContestChoice::new(200, "No"),
+        ContestChoice::new(300, "Maybe"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(2)
+        .build();
+
+    let vote = FlatVote::from(DecodedContestVote::new(&contest, vec![
+        DecodedVoteChoice::new(choices[0].clone()),
+        DecodedVoteChoice::new(choices[2].clone()),
+    ]));
+    assert_eq!(2, vote.choice_count());
+    let ids: Vec<i64> = vote.choices_iter().map(|c| c.contest_choice.id).collect();
+    assert_eq!(vec![100, 300], ids);
+    assert!(vote.contains_choice_id(300));
+    assert!(!vote.contains_choice_id(200));
+
+    let blank = FlatVote::blank(&contest);
+    assert_eq!(0, blank.choice_count());
+    assert_eq!(0, blank.choices_iter().count());
+
+}
+
+
+#[test]
+fn check_choices_before_voting() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Yes"),
+        ContestChoice::new(200, "No"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .build();
+
+    assert!(contest.is_choice_valid(&choices[1]));
+    assert!(!contest.is_choice_valid(&ContestChoice::new(300, "Maybe")));
+    assert!(!contest.is_choice_valid(&ContestChoice::write_in()));
+
+    let submitted = vec![
+        DecodedVoteChoice::new(choices[0].clone()),
+        DecodedVoteChoice::new(ContestChoice::new(300, "Maybe")),
+        DecodedVoteChoice::new(ContestChoice::new(400, "Never")),
+    ];
+    assert_eq!(Ok(()), contest.are_choices_valid(&submitted[..1]));
+    assert_eq!(Err(vec![300, 400]), contest.are_choices_valid(&submitted));
+
+    // Write-ins are fine where allowed
+    let contest = ContestBuilder::new(1, &choices)
+        .allows_write_ins(true)
+        .build();
+    assert!(contest.are_choices_valid(&[DecodedVoteChoice::write_in("Perhaps")]).is_ok());
+
+}
+
+
+#[test]
+fn contests_as_map_keys() {
+
+    let contest = gen_random_contest(3, gen_random_choices(10));
+    let other_contest = gen_random_contest(2, gen_random_choices(5));
+
+    let mut votes: HashMap<Contest, Vec<FlatVote>> = HashMap::new();
+    votes.insert(contest.clone(), gen_random_votes(10, &contest));
+    votes.insert(other_contest.clone(), gen_random_votes(5, &other_contest));
+
+    assert_eq!(10, votes[&contest].len());
+    assert_eq!(5, votes[&other_contest].len());
+
+}
+
+
+#[test]
+fn disqualify_choices_after_count() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Alice"),
+        ContestChoice::new(200, "Bob"),
+        ContestChoice::new(300, "Carol"),
+    ];
+    let mut contest = ContestBuilder::new(1, &choices)
+        .min_choices(1)
+        .max_choices(2)
+        .build();
+
+    let vote_for = |contest: &Contest, idx: &[usize]| -> FlatVote {
+        let choices = idx.iter().map(|i| DecodedVoteChoice::new(choices[*i].clone())).collect();
+        DecodedContestVote::new(contest, choices).into()
+    };
+    let votes = vec![
+        vote_for(&contest, &[0]),
+        vote_for(&contest, &[0]),
+        vote_for(&contest, &[0, 1]),
+        vote_for(&contest, &[1]),
+        vote_for(&contest, &[2]),
+    ];
+
+    let tally = Tally::new(&contest).with_votes(votes.clone());
+    assert_eq!(vec![choices[0].clone()], tally.result().winners);
+    assert!(tally.result().disqualified_choices.is_empty());
+
+    // Votes including an excluded choice are not counted
+    let result = tally.result_excluding(&[100]);
+    assert_eq!(2, result.total_valid_votes);
+    assert_eq!(vec![choices[0].clone()], result.disqualified_choices);
+    assert_eq!(None, result.choice_result_for(100));
+    assert_eq!(1, result.choice_result_for(200).expect("Failed to find Bob's result").total_count);
+
+    // Disqualifying in the contest has the same effect on every count
+    assert!(!contest.disqualify_choice(400));
+    assert!(contest.disqualify_choice(100));
+    assert!(contest.disqualify_choice(100));
+    assert_eq!(&[100], contest.disqualified_choice_ids());
+    let tally = Tally::new(&contest).with_votes(votes);
+    assert_eq!(result, tally.result());
+
+}
+
+
+#[test]
+fn disqualified_choices_in_every_count() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Alice"),
+        ContestChoice::new(200, "Bob"),
+    ];
+    let mut contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .build();
+    contest.disqualify_choice(100);
+
+    let vote_for = |i: usize| -> FlatVote {
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[i].clone())]).into()
+    };
+    let mut provisional = vote_for(1);
+    provisional.mark_provisional();
+    let votes = vec![vote_for(0), vote_for(0), vote_for(1), provisional];
+    let tally = Tally::new(&contest).with_votes(votes);
+
+    let result = tally.result();
+    assert_eq!(vec![choices[1].clone()], result.winners);
+    assert_eq!(vec![choices[0].clone()], result.disqualified_choices);
+    assert_eq!(result, tally.result_with_mode(TallyMode::ExcludeProvisional));
+
+    let result = tally.result_with_mode(TallyMode::IncludeProvisional);
+    assert_eq!(2, result.total_valid_votes);
+    assert_eq!(vec![choices[0].clone()], result.disqualified_choices);
+
+    #[cfg(feature = "rayon")]
+    assert_eq!(tally.result(), tally.result_parallel());
+
+}
+
+
+#[test]
+fn contests_are_equal_by_id() {
+
+    let choices = gen_random_choices(3);
+    let contest = ContestBuilder::new(1, &choices)
+        .custom_id(42)
+        .description("Before")
+        .build();
+    let updated = ContestBuilder::from_existing(&contest)
+        .custom_id(ContestId(42))
+        .description("After")
+        .build();
+
+    // Same contest, different content
+    assert_eq!(ContestId(42), updated.id());
+    assert_eq!(contest, updated);
+    assert!(!contest.content_eq(&updated));
+    assert!(contest.content_eq(&contest.clone()));
+
+    let other = ContestBuilder::from_existing(&contest)
+        .custom_id(43)
+        .build();
+    assert_ne!(contest, other);
+
+}
+
+
+#[test]
+fn contest_with_provisional_votes() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Yes"),
+        ContestChoice::new(200, "No"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+
+    let mut votes: Vec<FlatVote> = vec![
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[0].clone())]).into(),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[1].clone())]).into(),
+        DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[1].clone())]).into(),
+    ];
+    votes[1].mark_provisional();
+    votes[2].mark_provisional();
+    let mut tally = Tally::new(&contest).with_votes(votes);
+    assert_eq!(2, tally.provisional_count());
+
+    // Provisional votes are reported but not counted by default
+    let result = tally.result_with_mode(TallyMode::ExcludeProvisional);
+    assert_eq!(1, result.total_valid_votes);
+    assert_eq!(2, result.provisional_votes);
+    assert_eq!(100, result.winners[0].id);
+
+    let result = tally.result_with_mode(TallyMode::IncludeProvisional);
+    assert_eq!(3, result.total_valid_votes);
+    assert_eq!(2, result.provisional_votes);
+    assert_eq!(200, result.winners[0].id);
+
+    // Vote queries skip provisional votes as well
+    let result = tally.result();
+    assert_eq!(result.total_valid_votes as usize, tally.valid_vote_count());
+    assert!(tally.valid_votes().all(|v| !v.is_provisional()));
+    assert_eq!(tally.count_for(200) as usize, tally.votes_for_choice_id(200).len());
+    assert!(tally.votes_for_choice_id(200).is_empty());
+    assert_eq!(1, tally.votes_for_choice_id(100).len());
+
+    // Validated votes are no longer provisional
+    let mut vote = FlatVote::from(DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[1].clone())]));
+    vote.mark_provisional();
+    vote.clear_provisional();
+    tally.add_vote(vote);
+    assert_eq!(2, tally.result().total_valid_votes);
+    assert_eq!(2, tally.valid_vote_count());
+    assert_eq!(1, tally.votes_for_choice_id(200).len());
+
+}
+
+
+#[test]
+fn random_votes_with_invalid_ratio() {
+
+    let choices = gen_random_choices(10);
+    let contest = ContestBuilder::new(3, &choices)
+        .max_choices(3)
+        .min_choices(2)
+        .build();
+
+    for ratio in [0.0, 0.25, 0.5, 1.0] {
+        let tally = Tally::new(&contest)
+            .with_votes(gen_random_votes_with_invalid_ratio(200, &contest, ratio));
+        let result = tally.result();
+        assert_eq!((200.0 * ratio) as i64, result.total_invalid_votes);
+        assert_eq!(200 - (200.0 * ratio) as i64, result.total_valid_votes);
+    }
+
+}
+
+
+#[test]
+fn reset_and_replace_votes() {
+
+    let contest = gen_random_contest(3, gen_random_choices(10));
+    let mut tally = Tally::new(&contest)
+        .with_votes(gen_random_votes(20, &contest));
+
+    tally.replace_votes(gen_random_votes(5, &contest));
+    assert_eq!(5, tally.votes().len());
+
+    tally.reset();
+    assert!(tally.votes().is_empty());
+    assert_eq!(contest.id(), tally.contest().id());
+
+}
+
+
+#[test]
+fn compare_and_clone_results() {
+
+    let contest = gen_random_contest(3, gen_random_choices(10));
+    let tally = Tally::new(&contest)
+        .with_votes(gen_random_votes(100, &contest));
+
+    let result = tally.result();
+    assert_eq!(result, tally.result());
+    assert_eq!(result, result.clone());
+
+    let mut other = result.clone();
+    other.total_invalid_votes += 1;
+    assert_ne!(result, other);
+
+}
+
+
+#[test]
+fn random_choices_have_unique_ids() {
+
+    let choices = gen_random_choices(50);
+    let ids: HashSet<i64> = choices.iter().map(|c| c.id).collect();
+    assert_eq!(50, ids.len());
+
+}
+
+
+#[test]
+fn random_choices_with_custom_names() {
+
+    let cities = ["Lisbon", "Porto", "Braga"];
+    let choices = gen_random_choices_from(20, &cities);
+    assert_eq!(20, choices.len());
+    assert!(choices.iter().all(|c| cities.contains(&c.text.as_str())));
+    let ids: HashSet<i64> = choices.iter().map(|c| c.id).collect();
+    assert_eq!(20, ids.len());
+
+}
+
+
+#[test]
+fn build_contest_choice_by_choice() {
+
+    let contest = ContestBuilder::default()
+        .add_choice(ContestChoice::new(1, "Alice"))
+        .add_choice(ContestChoice::new(2, "Bob"))
+        .build();
+
+    assert_eq!(1, contest.num_winners());
+    assert_eq!(vec![1, 2], contest.choices().iter().map(|c| c.id).collect::<Vec<i64>>());
+
+    let from_slice = ContestBuilder::new(1, contest.choices())
+        .custom_id(contest.id())
+        .build();
+    assert_eq!(contest, from_slice);
+    assert!(contest.content_eq(&from_slice));
+
+    // Single-choice votes are valid by default
+    assert_eq!(1, contest.min_choices());
+    assert_eq!(1, contest.max_choices());
+    let vote = DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(contest.choices()[1].clone())]);
+    assert!(!vote.is_invalid());
+    let result = Tally::new(&contest).with_votes(vec![vote.into()]).result();
+    assert_eq!(1, result.total_valid_votes);
+    assert_eq!(0, result.total_invalid_votes);
+    assert_eq!(vec![contest.choices()[1].clone()], result.winners);
+
+}
+
+
+#[test]
+fn weighted_votes() {
+
+    let choices = vec![
+        ContestChoice::new(1, "Alice"),
+        ContestChoice::new(2, "Bob"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .build();
+
+    // A board member with three votes against two regular voters
+    let board = DecodedContestVote::new(&contest, vec![DecodedVoteChoice::with_weight(choices[0].clone(), 3)]);
+    let regular = DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[1].clone())]);
+    let tally = Tally::new(&contest)
+        .with_votes(vec![board.into(), regular.clone().into(), regular.into()]);
+
+    assert_eq!(3, tally.count_for(1));
+    assert_eq!(2, tally.count_for(2));
+    assert_eq!(vec![choices[0].clone()], tally.result().winners);
+
+}
+
+
+#[test]
+fn look_up_choices_by_id() {
+
+    let contest = gen_random_contest(1, gen_random_choices(10));
+
+    let map = contest.choices_as_map();
+    assert_eq!(10, map.len());
+    for choice in contest.choices() {
+        assert_eq!(Some(choice), contest.choice_by_id(choice.id));
+        assert_eq!(Some(&choice), map.get(&choice.id));
+    }
+    assert_eq!(None, contest.choice_by_id(-1));
+
+}
+
+
+/// Plurality count where the least voted choices win
+struct LeastVoted;
+
+impl TallyMethod for LeastVoted {
+
+    fn name(&self) -> &str {
+        "least-voted"
+    }
+
+    fn tally(&self, contest: &Contest, votes: &[FlatVote]) -> ContestResult {
+        let mut result = PluralityAtLarge.tally(contest, votes);
+        result.winners = result.results.iter().rev()
+            .take(contest.num_winners() as usize)
+            .map(|r| r.contest_choice.clone())
+            .collect();
+        result
+    }
+
+}
+
+#[test]
+fn custom_tally_method() {
+
+    let choices = vec![
+        ContestChoice::new(1, "Alice"),
+        ContestChoice::new(2, "Bob"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .tally_method(LeastVoted)
+        .max_choices(1)
+        .build();
+    assert_eq!("least-voted", contest.tally_type());
+    assert_eq!("least-voted", contest.tally_method().name());
+
+    let votes: Vec<FlatVote> = [0, 0, 1].iter()
+        .map(|i| DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[*i].clone())]).into())
+        .collect();
+
+    // The method of the contest is used by default, and kept in its copies
+    let least_voted = Tally::new(&contest).with_votes(votes.clone());
+    assert_eq!("least-voted", least_voted.method().name());
+    assert_eq!(vec![choices[1].clone()], least_voted.result().winners);
+    assert_eq!(vec![choices[1].clone()], least_voted.result_with_mode(TallyMode::IncludeProvisional).winners);
+    let copy = ContestBuilder::from_existing(&contest).build();
+    assert_eq!(vec![choices[1].clone()], Tally::new(&copy).with_votes(votes.clone()).result().winners);
+
+    // Unless a different one is given
+    let plurality = Tally::with_method(&contest, PluralityAtLarge).with_votes(votes.clone());
+    assert_eq!(vec![choices[0].clone()], plurality.result().winners);
+
+    // Contests without a method, e.g. loaded from a file, are counted with plurality
+    let mut contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .build();
+    assert_eq!("plurality-at-large", contest.tally_method().name());
+    assert_eq!(vec![choices[0].clone()], Tally::new(&contest).with_votes(votes.clone()).result().winners);
+    contest = serde_json::from_str(&serde_json::to_string(&contest).expect("Failed to encode contest").replace("plurality-at-large", ""))
+        .expect("Failed to decode contest");
+    assert_eq!("", contest.tally_type());
+    assert_eq!(vec![choices[0].clone()], Tally::new(&contest).with_votes(votes).result().winners);
+
+}
+
+
+#[test]
+fn expressed_votes_per_ballot() {
+
+    let choices = vec![
+        ContestChoice::new(1, "Alice"),
+        ContestChoice::new(2, "Bob"),
+        ContestChoice::new(3, "Carol"),
+    ];
+    let contest = ContestBuilder::new(2, &choices)
+        .max_choices(2)
+        .build();
+
+    let tally = Tally::new(&contest);
+    assert_eq!(0, tally.result().total_expressed_votes());
+    assert_eq!(0.0, tally.result().average_choices_per_ballot());
+
+    let both = DecodedContestVote::new(&contest, vec![
+        DecodedVoteChoice::new(choices[0].clone()),
+        DecodedVoteChoice::new(choices[1].clone()),
+    ]);
+    let one = DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[2].clone())]);
+    let tally = tally.with_votes(vec![both.into(), one.into()]);
+
+    let result = tally.result();
+    assert_eq!(3, result.total_expressed_votes());
+    assert_eq!(1.5, result.average_choices_per_ballot());
+
+}
+
+
+#[test]
+fn modify_existing_contest() {
+
+    let contest = ContestBuilder::new(2, &gen_random_choices(5))
+        .description("Board election")
+        .max_choices(2)
+        .quorum(10)
+        .build();
+
+    let copy = ContestBuilder::from_existing(&contest)
+        .custom_id(contest.id())
+        .build();
+    assert_eq!(contest, copy);
+    assert!(contest.content_eq(&copy));
+
+    let modified = ContestBuilder::from_existing(&contest)
+        .custom_id(contest.id().0 + 1)
+        .add_choice(ContestChoice::new(1_000, "Newcomer"))
+        .build();
+    assert_eq!(6, modified.choices().len());
+    assert_eq!(contest.max_choices(), modified.max_choices());
+    assert_eq!(contest.quorum(), modified.quorum());
+
+}
+
+
+/// A valid vote ranking `choices` in order of preference
+fn ranked_vote(contest: &Contest, choices: &[&ContestChoice]) -> FlatVote {
+    let ranked = choices.iter().enumerate()
+        .map(|(rank, c)| DecodedVoteChoice::with_weight((*c).clone(), rank as u64 + 1))
+        .collect();
+    DecodedContestVote::new(contest, ranked).into()
+}
+
+#[test]
+fn condorcet_winner() {
+
+    let choices = vec![
+        ContestChoice::new(1, "Alice"),
+        ContestChoice::new(2, "Bob"),
+        ContestChoice::new(3, "Carol"),
+    ];
+    let (a, b, c) = (&choices[0], &choices[1], &choices[2]);
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(3)
+        .build();
+
+    // Bob beats both Alice and Carol head to head
+    let tally = Tally::new(&contest).with_votes(vec![
+        ranked_vote(&contest, &[a, b, c]),
+        ranked_vote(&contest, &[b, c, a]),
+        ranked_vote(&contest, &[c, b]),
+    ]);
+    let matrix = tally.condorcet_matrix();
+    assert_eq!(Some(&2), matrix.get(&(2, 1)));
+    assert_eq!(Some(&1), matrix.get(&(1, 2)));
+    assert_eq!(Some(&2), matrix.get(&(2, 3)));
+    assert_eq!(Some(b), tally.condorcet_winner());
+
+    // Condorcet paradox: A > B, B > C and C > A
+    let tally = Tally::new(&contest).with_votes(vec![
+        ranked_vote(&contest, &[a, b, c]),
+        ranked_vote(&contest, &[b, c, a]),
+        ranked_vote(&contest, &[c, a, b]),
+    ]);
+    assert_eq!(None, tally.condorcet_winner());
+
+}
+
+
+#[test]
+fn result_as_of_cutoff() {
+
+    use chrono::{Duration, TimeZone, Utc};
+
+    let choices = vec![
+        ContestChoice::new(1, "Alice"),
+        ContestChoice::new(2, "Bob"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .build();
+    let vote = |i: usize| FlatVote::from(DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[i].clone())]));
+
+    let midnight = Utc.with_ymd_and_hms(2024, 11, 6, 0, 0, 0).unwrap();
+    let tally = Tally::new(&contest).with_votes(vec![
+        vote(0),
+        vote(0).with_submitted_at(midnight - Duration::hours(1)),
+        vote(1).with_submitted_at(midnight),
+        vote(1).with_submitted_at(midnight + Duration::seconds(1)),
+        vote(1).with_submitted_at(midnight + Duration::hours(2)),
+    ]);
+
+    let result = tally.result_as_of(midnight);
+    assert_eq!(3, result.total_valid_votes);
+    assert_eq!(vec![choices[0].clone()], result.winners);
+    assert_eq!(vec![choices[1].clone()], tally.result().winners);
+
+}
+
+
+#[test]
+fn contest_choices_from_tuples() {
+
+    let choices: Vec<ContestChoice> = vec![(1, "Alice"), (2, "Bob")].into_iter()
+        .map(Into::into)
+        .collect();
+    assert_eq!(vec![ContestChoice::new(1, "Alice"), ContestChoice::new(2, "Bob")], choices);
+
+    let owned = ContestChoice::from((3, String::from("Carol")));
+    assert_eq!(ContestChoice::new(3, "Carol"), owned);
+
+    let (id, text): (i64, String) = owned.into();
+    assert_eq!((3, "Carol"), (id, text.as_str()));
+
+}
+
+
+#[test]
+fn contest_choice_urls() {
+
+    let choice = ContestChoice::new(1, "Alice");
+    assert_eq!(None, choice.primary_url());
+    assert!(choice.secondary_urls().is_empty());
+
+    let choice = choice
+        .with_url("https://example.com/alice/news")
+        .with_primary_url("https://example.com/alice");
+    assert_eq!(Some("https://example.com/alice"), choice.primary_url());
+    assert_eq!(&["https://example.com/alice/news".to_string()], choice.secondary_urls());
+
+}
+
+
+#[test]
+fn flat_vote_conversions() {
+
+    let choices = vec![
+        ContestChoice::new(1, "Alice"),
+        ContestChoice::new(2, "Bob"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .build();
+    let decoded = DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[1].clone())]);
+
+    // Flattening by reference leaves the original vote in place
+    let flat = FlatVote::from(&decoded);
+    assert_eq!(flat, FlatVote::from(decoded.clone()));
+    assert_eq!(contest.id(), flat.contest_id());
+
+    let rebuilt = DecodedContestVote::try_from((flat.clone(), &contest))
+        .expect("Failed to rebuild vote");
+    assert_eq!(decoded.choices, rebuilt.choices);
+    assert_eq!(contest, rebuilt.contest);
+    assert_eq!(VoteValidity::Valid, rebuilt.validity);
+
+    // Choices must belong to the contest
+    let other = ContestBuilder::new(1, &[ContestChoice::new(3, "Carol")])
+        .custom_id(contest.id())
+        .build();
+    assert!(matches!(
+        DecodedContestVote::try_from((flat.clone(), &other)),
+        Err(Error::UnknownChoiceId(2))
+    ));
+
+    // And so must the vote
+    let other = ContestBuilder::new(1, &choices)
+        .custom_id(contest.id().0 + 1)
+        .build();
+    assert!(matches!(
+        DecodedContestVote::try_from((flat, &other)),
+        Err(Error::ContestMismatch{ expected, actual }) if expected == other.id() && actual == contest.id()
+    ));
+
+}
+
+
+#[test]
+fn votes_only_accepted_while_open() {
+
+    let mut contest = ContestBuilder::new(1, &gen_random_choices(3))
+        .phase(ContestPhase::Draft)
+        .build();
+    let vote = FlatVote::blank(&contest);
+
+    let mut tally = Tally::new(&contest);
+    assert!(matches!(tally.add_vote_checked(vote.clone()), Err(Error::ContestNotOpen)));
+
+    contest.set_phase(ContestPhase::Open);
+    let mut tally = Tally::new(&contest);
+    tally.add_vote_checked(vote.clone())
+        .expect("Failed to add vote to open contest");
+    assert_eq!(1, tally.votes().len());
+
+    contest.set_phase(ContestPhase::Closed);
+    let mut tally = Tally::new(&contest);
+    assert!(matches!(tally.add_vote_checked(vote), Err(Error::ContestNotOpen)));
+    assert!(tally.votes().is_empty());
+
+    // Contests are open unless stated otherwise
+    assert_eq!(ContestPhase::Open, gen_random_contest(1, gen_random_choices(3)).phase());
+
+}
+
+
+#[test]
+fn random_votes_with_bias() {
+
+    let contest = ContestBuilder::new(1, &gen_random_choices(3))
+        .min_choices(1)
+        .max_choices(1)
+        .build();
+
+    let votes = gen_random_votes_biased(1_000, &contest, &[8.0, 2.0, 0.0])
+        .expect("Failed to generate biased votes");
+    let tally = Tally::new(&contest).with_votes(votes);
+    let ids: Vec<i64> = contest.choices().iter().map(|c| c.id).collect();
+    assert_eq!(1_000, tally.valid_vote_count());
+    assert!(tally.count_for(ids[0]) > tally.count_for(ids[1]));
+    assert_eq!(0, tally.count_for(ids[2]));
+
+    assert!(matches!(gen_random_votes_biased(10, &contest, &[1.0, 1.0]), Err(Error::InvalidWeights(_))));
+    assert!(matches!(gen_random_votes_biased(10, &contest, &[1.0, -1.0, 1.0]), Err(Error::InvalidWeights(_))));
+
+}
+
+
+#[test]
+fn choices_grouped_by_category() {
+
+    let choices = vec![
+        ContestChoice::new(1, "Alice").with_category("Greens"),
+        ContestChoice::new(2, "Bob").with_category("Reds"),
+        ContestChoice::new(3, "Carol").with_category("Greens"),
+        ContestChoice::new(4, "Dave"),
+    ];
+    let contest = ContestBuilder::new(2, &choices)
+        .max_choices(1)
+        .build();
+
+    let groups = contest.choices_by_category();
+    assert_eq!(3, groups.len());
+    assert_eq!(vec![&choices[0], &choices[2]], groups[&Some("Greens".to_string())]);
+    assert_eq!(vec![&choices[3]], groups[&None]);
+
+    let votes = [0, 2, 2, 1].iter()
+        .map(|i| DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[*i].clone())]).into())
+        .collect();
+    let result = Tally::new(&contest).with_votes(votes).result();
+    let groups = result.results_by_category();
+    let greens: Vec<i64> = groups[&Some("Greens".to_string())].iter().map(|r| r.contest_choice.id).collect();
+    assert_eq!(vec![3, 1], greens);
+    assert_eq!(1, groups[&Some("Reds".to_string())].len());
+
+}
+
+
+#[test]
+fn iterate_over_tally() {
+
+    let contest = gen_random_contest(1, gen_random_choices(5));
+    let mut tally = Tally::new(&contest)
+        .with_votes(gen_random_votes(10, &contest));
+
+    for vote in &mut tally {
+        vote.invalidate();
+    }
+
+    let mut count = 0;
+    for vote in &tally {
+        assert!(vote.is_invalid());
+        count += 1;
+    }
+    assert_eq!(10, count);
+
+}
+
+
+#[test]
+fn contest_and_result_timestamps() {
+
+    use chrono::Utc;
+
+    let before = Utc::now();
+    let contest = gen_random_contest(1, gen_random_choices(3));
+    let result = Tally::new(&contest)
+        .with_votes(gen_random_votes(10, &contest))
+        .result();
+    let after = Utc::now();
+
+    assert!(before <= contest.created_at() && contest.created_at() <= after);
+    assert!(contest.created_at() <= result.calculated_at && result.calculated_at <= after);
+    assert!(result.age() >= chrono::Duration::zero());
+    assert!(result.age() <= Utc::now() - before);
+
+    // Saved as seconds since the epoch
+    let json = contest.to_json()
+        .expect("Failed to encode contest");
+    let value: serde_json::Value = serde_json::from_str(&json)
+        .expect("Failed to parse contest");
+    assert_eq!(Some(contest.created_at().timestamp()), value["created_at"].as_i64());
+    let loaded = Contest::from_json(&json)
+        .expect("Failed to decode contest");
+    assert_eq!(contest.created_at().timestamp(), loaded.created_at().timestamp());
+    assert!(contest.content_eq(&loaded));
+
+    // Older files without timestamps still load
+    let legacy: String = json.lines()
+        .filter(|l| !l.contains("created_at"))
+        .collect::<Vec<&str>>()
+        .join("\n")
+        .replace(",\n}", "\n}");
+    let loaded = Contest::from_json(&legacy)
+        .expect("Failed to decode contest without creation time");
+    assert_eq!(0, loaded.created_at().timestamp());
+
+}
+
+
+#[test]
+fn participation_rates() {
+
+    let choices = gen_random_choices(3);
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .eligible_voters(10)
+        .build();
+
+    let vote = FlatVote::from(DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[0].clone())]));
+    let result = Tally::new(&contest)
+        .with_votes(vec![vote.clone(), vote.clone(), vote, FlatVote::blank(&contest)])
+        .result();
+
+    assert_eq!(Some(0.4), result.participation_rate(10));
+    assert_eq!(Some(0.3), result.valid_participation_rate(10));
+    assert_eq!(None, result.participation_rate(0));
+    assert_eq!(Some(0.4), result.turnout);
+
+    // Turnout is unknown without eligible voters
+    let contest = ContestBuilder::from_existing(&contest).build();
+    assert_eq!(Some(10), contest.eligible_voters());
+    let contest = ContestBuilder::new(1, &choices).build();
+    assert_eq!(None, Tally::new(&contest).result().turnout);
+
+}
+
+
+#[test]
+fn decisive_results() {
+
+    let choices = vec![
+        ContestChoice::new(1, "Alice"),
+        ContestChoice::new(2, "Bob"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .build();
+    let votes_for = |counts: [usize; 2]| -> Vec<FlatVote> {
+        counts.iter().enumerate()
+            .flat_map(|(i, n)| (0..*n).map(move |_| i))
+            .map(|i| DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[i].clone())]).into())
+            .collect()
+    };
+
+    // 60% against 40%
+    let result = Tally::new(&contest).with_votes(votes_for([6, 4])).result();
+    assert!(result.is_decisive(20.0));
+    assert!(!result.is_decisive(25.0));
+    assert!((result.confidence_level() - 0.2).abs() < 1e-9);
+
+    // Ties are never decisive
+    let result = Tally::new(&contest).with_votes(votes_for([5, 5])).result();
+    assert!(!result.is_decisive(0.0));
+    assert_eq!(0.0, result.confidence_level());
+
+}
+
+
+#[test]
+fn projected_winner_of_partial_count() {
+
+    let choices = vec![
+        ContestChoice::new(1, "Alice"),
+        ContestChoice::new(2, "Bob"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .build();
+    let votes_for = |counts: [usize; 2]| -> Vec<FlatVote> {
+        counts.iter().enumerate()
+            .flat_map(|(i, n)| (0..*n).map(move |_| i))
+            .map(|i| DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[i].clone())]).into())
+            .collect()
+    };
+
+    // Alice leads by 20 out of 100 votes
+    let result = Tally::new(&contest).with_votes(votes_for([60, 40])).result();
+    assert_eq!(Some(&choices[0]), result.projected_winner(0.9));
+    assert_eq!(Some(&choices[0]), result.projected_winner(1.0));
+    assert_eq!(None, result.projected_winner(0.75));
+    assert_eq!(None, result.projected_winner(0.5));
+
+    // Ties are never projected
+    let result = Tally::new(&contest).with_votes(votes_for([5, 5])).result();
+    assert_eq!(None, result.projected_winner(1.0));
+
+}
+
+
+#[test]
+fn validate_decoded_votes() {
+
+    let choices = gen_random_choices(3);
+    let contest = ContestBuilder::new(1, &choices)
+        .min_choices(1)
+        .max_choices(1)
+        .build();
+
+    let one = vec![DecodedVoteChoice::new(choices[0].clone())];
+    let two = vec![DecodedVoteChoice::new(choices[0].clone()), DecodedVoteChoice::new(choices[1].clone())];
+    assert!(DecodedContestVote::validate(&contest, &one));
+    assert!(!DecodedContestVote::validate(&contest, &two));
+
+    let vote = DecodedContestVote::new(&contest, one);
+    assert!(vote.is_valid_vote() && !vote.is_invalid());
+    let vote = DecodedContestVote::new(&contest, two);
+    assert!(!vote.is_valid_vote() && vote.is_invalid());
+
+}
+
+
+#[test]
+fn invalid_vote_reasons() {
+
+    let choices = gen_random_choices(3);
+    let contest = ContestBuilder::new(1, &choices)
+        .min_choices(1)
+        .max_choices(2)
+        .build();
+    let vote_for = |choices: &[ContestChoice]| {
+        let choices = choices.iter().cloned().map(DecodedVoteChoice::new).collect();
+        DecodedContestVote::new(&contest, choices)
+    };
+
+    let mut vote = vote_for(&choices[..1]);
+    assert_eq!(VoteValidity::Valid, vote.validity);
+    assert_eq!(None, vote.reason());
+    vote.invalidate();
+    assert_eq!(Some(InvalidReason::ExplicitlyInvalidated), vote.reason());
+
+    assert_eq!(Some(InvalidReason::Undervote), vote_for(&[]).reason());
+    assert_eq!(Some(InvalidReason::Overvote), vote_for(&choices).reason());
+    let unknown = ContestChoice::new(choices.iter().map(|c| c.id).max().unwrap() + 1, "Nobody");
+    assert_eq!(
+        Some(InvalidReason::ChoiceNotInContest(unknown.id)),
+        vote_for(&[choices[0].clone(), unknown]).reason()
+    );
+
+    // Explicitly invalid flat votes are rebuilt with a reason
+    let flat = FlatVote::from(&vote);
+    assert!(flat.is_invalid());
+    let rebuilt = DecodedContestVote::try_from((flat, &contest))
+        .expect("Failed to rebuild vote");
+    assert_eq!(VoteValidity::Invalid(InvalidReason::ExplicitlyInvalidated), rebuilt.validity);
+
+}
+
+
+#[test]
+fn legacy_vote_validity() {
+
+    let choices = gen_random_choices(3);
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .build();
+    let legacy = |is_explicit_invalid: bool, choices: &[ContestChoice]| {
+        let choices: Vec<DecodedVoteChoice> = choices.iter().cloned().map(DecodedVoteChoice::new).collect();
+        serde_json::from_value::<DecodedContestVote>(serde_json::json!({
+            "is_explicit_invalid": is_explicit_invalid,
+            "choices": choices,
+            "contest": contest,
+        })).expect("Failed to decode legacy vote")
+    };
+
+    // Votes written with `is_explicit_invalid` get a validity
+    assert_eq!(VoteValidity::Valid, legacy(false, &choices[..1]).validity);
+    assert_eq!(Some(InvalidReason::ExplicitlyInvalidated), legacy(true, &choices[..1]).reason());
+    assert_eq!(Some(InvalidReason::Overvote), legacy(false, &choices).reason());
+
+}
+
+
+#[test]
+fn invalid_vote_breakdown() {
+
+    let choices = gen_random_choices(4);
+    let contest = ContestBuilder::new(1, &choices)
+        .min_choices(2)
+        .max_choices(3)
+        .build();
+    let vote_for = |n: usize| -> FlatVote {
+        let choices = choices[..n].iter().cloned().map(DecodedVoteChoice::new).collect();
+        DecodedContestVote::new(&contest, choices).into()
+    };
+
+    let mut invalidated = vote_for(2);
+    invalidated.invalidate();
+    let votes = vec![vote_for(1), vote_for(0), vote_for(2), vote_for(3), vote_for(4), invalidated];
+    let result = Tally::new(&contest).with_votes(votes).result();
+
+    assert_eq!(4, result.total_invalid_votes);
+    assert_eq!(2, result.invalid_breakdown.undervotes);
+    assert_eq!(1, result.invalid_breakdown.overvotes);
+    assert_eq!(1, result.invalid_breakdown.other);
+
+    // Blank votes are only counted apart if they meet the contest rules
+    let result = Tally::new(&contest).with_votes(vec![FlatVote::blank(&contest)]).result();
+    assert_eq!((1, 0), (result.invalid_breakdown.undervotes, result.invalid_breakdown.other));
+    let contest = ContestBuilder::from_existing(&contest)
+        .min_choices(0)
+        .build();
+    let result = Tally::new(&contest).with_votes(vec![FlatVote::blank(&contest)]).result();
+    assert_eq!((0, 1), (result.invalid_breakdown.undervotes, result.invalid_breakdown.other));
+
+}
+
+
+#[test]
+fn what_if_analysis() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Alice"),
+        ContestChoice::new(200, "Bob"),
+        ContestChoice::new(300, "Carol"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .build();
+    let votes_for = |i: usize, n: usize| -> Vec<FlatVote> {
+        (0..n)
+            .map(|_| DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[i].clone())]).into())
+            .collect()
+    };
+
+    let mut votes = votes_for(0, 5);
+    votes.extend(votes_for(1, 3));
+    votes.extend(votes_for(2, 1));
+    let tally = Tally::new(&contest).with_votes(votes);
+
+    let simulated = tally.simulate_with_extra_votes(votes_for(1, 3));
+    assert_eq!(vec![choices[1].clone()], simulated.winners);
+    assert_eq!(9, tally.votes().len());
+    assert_eq!(vec![choices[0].clone()], tally.result().winners);
+
+    // Bob loses a tie with Alice, so he needs one more vote than her
+    assert_eq!(Some(3), tally.votes_needed_for_choice_to_reach_position(200, 1));
+    assert_eq!(Some(5), tally.votes_needed_for_choice_to_reach_position(300, 1));
+    assert_eq!(Some(0), tally.votes_needed_for_choice_to_reach_position(100, 1));
+    assert_eq!(None, tally.votes_needed_for_choice_to_reach_position(200, 2));
+    assert_eq!(None, tally.votes_needed_for_choice_to_reach_position(400, 1));
+
+}
+
+
+#[test]
+fn customize_random_contest() {
+
+    let contest = gen_random_contest_builder(3, gen_random_choices(10))
+        .description("A custom random contest")
+        .quorum(50)
+        .build();
+
+    assert_eq!(3, contest.num_winners());
+    assert_eq!(Some(50), contest.quorum());
+    assert!(contest.min_choices() >= 1 && contest.min_choices() <= contest.max_choices());
+    assert!(contest.to_string().contains("A custom random contest"));
+
+}
+
+
+#[test]
+fn detect_duplicate_voters() {
+
+    let contest = gen_random_contest(1, gen_random_choices(5));
+    let mut votes = gen_random_votes(4, &contest);
+    votes[0] = votes[0].clone().with_voter_id("alice");
+    votes[1] = votes[1].clone().with_voter_id("bob");
+    votes[2] = votes[2].clone().with_voter_id("alice");
+    let tally = Tally::new(&contest).with_votes(votes);
+
+    assert!(tally.has_vote_from_voter("alice"));
+    assert!(tally.has_vote_from_voter("bob"));
+    assert!(!tally.has_vote_from_voter("carol"));
+    assert_eq!(2, tally.vote_count_from_voter("alice"));
+    assert_eq!(1, tally.vote_count_from_voter("bob"));
+    assert_eq!(0, tally.vote_count_from_voter("carol"));
+
+}
+
+
+#[test]
+fn build_flat_votes() {
+
+    let choices = vec![
+        ContestChoice::new(1, "Alice"),
+        ContestChoice::new(2, "Bob"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .build();
+
+    // Same vote as going through a decoded one
+    let vote = FlatVoteBuilder::for_contest(contest.id())
+        .add_choice(DecodedVoteChoice::new(choices[1].clone()))
+        .build();
+    let decoded = DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[1].clone())]);
+    assert_eq!(FlatVote::from(decoded), vote);
+
+    let vote = FlatVoteBuilder::for_contest(contest.id())
+        .voter_id("voter-1")
+        .mark_invalid()
+        .build();
+    assert_eq!(Some("voter-1"), vote.voter_id());
+    assert!(vote.is_invalid());
+    assert_eq!(0, vote.choice_count());
+
+    let tally = Tally::new(&contest).with_votes(vec![vote]);
+    assert_eq!(1, tally.result().total_invalid_votes);
+
+    // Nothing stops a choice that is not part of the contest, it is counted as invalid
+    let unknown = FlatVoteBuilder::for_contest(contest.id())
+        .add_choice(DecodedVoteChoice::new(ContestChoice::new(3, "Carol")))
+        .build();
+    let valid = FlatVoteBuilder::for_contest(contest.id())
+        .add_choice(DecodedVoteChoice::new(choices[0].clone()))
+        .build();
+    let result = Tally::new(&contest).with_votes(vec![unknown, valid]).result();
+    assert_eq!(1, result.total_valid_votes);
+    assert_eq!(1, result.total_invalid_votes);
+    assert_eq!(1, result.invalid_breakdown.not_in_contest);
+    assert_eq!(vec![choices[0].clone()], result.winners);
+    assert!(result.results.iter().all(|r| r.contest_choice.id != 3));
+
+}
+
+
+#[test]
+fn contest_predicates() {
+
+    let board = ContestBuilder::new(3, &gen_random_choices(5))
+        .max_choices(3)
+        .build();
+    assert!(board.is_multi_winner());
+    assert!(!board.is_single_winner());
+    assert!(board.allows_multiple_choices());
+    assert_eq!(5, board.choices_count());
+
+    let mayor = ContestBuilder::new(1, &gen_random_choices(4))
+        .max_choices(1)
+        .build();
+    assert!(!mayor.is_multi_winner());
+    assert!(mayor.is_single_winner());
+    assert!(!mayor.allows_multiple_choices());
+    assert_eq!(mayor.num_choices(), mayor.choices_count());
+
+}
+
+
+#[test]
+fn count_votes_by_source() {
+
+    let contest = gen_random_contest(1, gen_random_choices(5));
+    let sources = ["web", "paper", "sms"];
+    let mut votes = gen_random_votes_with_sources(30, &contest, &sources);
+    assert!(votes.iter().all(|v| v.source().is_some_and(|s| sources.contains(&s))));
+    votes.extend(gen_random_votes(5, &contest));
+    let tally = Tally::new(&contest).with_votes(votes);
+
+    let by_source = tally.votes_by_source();
+    assert!(by_source.len() <= sources.len() + 1);
+    assert_eq!(30, by_source.iter().filter(|(s, _)| s.is_some()).map(|(_, n)| n).sum::<usize>());
+    assert_eq!(Some(&5), by_source.get(&None));
+
+    // Sources survive a JSON round trip
+    let vote = gen_random_votes(1, &contest).remove(0).with_source("kiosk");
+    let json = serde_json::to_string(&vote).expect("Failed to encode vote");
+    let decoded: FlatVote = serde_json::from_str(&json).expect("Failed to decode vote");
+    assert_eq!(Some("kiosk"), decoded.source());
+
+}
+
+
+#[test]
+fn default_contest_is_a_placeholder() {
+
+    let contest = Contest::default();
+    assert_eq!(ContestId(0), contest.id());
+    assert_eq!(1, contest.num_winners());
+    assert_eq!((1, 1), (contest.min_choices(), contest.max_choices()));
+    assert_eq!("plurality-at-large", contest.tally_type());
+    assert!(contest.choices().is_empty());
+    assert!(contest.content_eq(&Contest::default()));
+
+    let result = Tally::new(&contest).result();
+    assert!(result.results.is_empty());
+    assert!(result.winners.is_empty());
+
+}
+
+
+#[test]
+fn exhaustive_ballot() {
+
+    let choices = vec![
+        ContestChoice::new(1, "Alice"),
+        ContestChoice::new(2, "Bob"),
+        ContestChoice::new(3, "Carol"),
+        ContestChoice::new(4, "Dave"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(2)
+        .build();
+    let vote = |ids: &[usize]| -> FlatVote {
+        DecodedContestVote::new(&contest, ids.iter().map(|i| DecodedVoteChoice::new(choices[*i].clone())).collect()).into()
+    };
+    let mut votes = vec![];
+    votes.extend((0..5).map(|_| vote(&[0])));
+    votes.extend((0..4).map(|_| vote(&[1])));
+    votes.extend((0..3).map(|_| vote(&[2, 1])));
+    let tally = Tally::new(&contest).with_votes(votes);
+
+    // Dave got no votes at all
+    let (next, eliminated) = tally.eliminate_last();
+    assert_eq!(choices[3], eliminated);
+    assert_eq!(contest.id(), next.contest().id());
+    assert_eq!(3, next.contest().num_choices());
+    assert_eq!(12, next.votes().len());
+
+    // Carol goes next, and her voters back Bob who then beats Alice
+    let result = tally.multi_round_result();
+    let eliminated: Vec<i64> = result.rounds.iter().map(|(_, c)| c.id).collect();
+    assert_eq!(vec![4, 3, 1], eliminated);
+    assert_eq!(choices[1], result.winner);
+    assert_eq!(7, result.rounds[2].0.choice_result_for(2).unwrap().total_count);
+
+}
+
+
+#[test]
+fn anonymized_results() {
+
+    let contest = gen_random_contest(2, gen_random_choices(8));
+    let result = Tally::new(&contest)
+        .with_votes(gen_random_votes(200, &contest))
+        .result();
+
+    let anonymized = result.anonymized();
+    assert_eq!(anonymized, result.anonymized());
+    assert_eq!(result.total_valid_votes, anonymized.total_valid_votes);
+    assert_eq!(result.winners.len(), anonymized.winners.len());
+
+    // Same counts, none of the original choices
+    let counts = |results: &[votes::ContestChoiceResult]| {
+        let mut counts: Vec<u64> = results.iter().map(|r| r.total_count).collect();
+        counts.sort_unstable();
+        counts
+    };
+    assert_eq!(counts(&result.results), counts(&anonymized.results));
+    for r in &anonymized.results {
+        assert!(r.contest_choice.text.starts_with("Candidate "));
+        assert!(contest.choice_by_id(r.contest_choice.id).is_none());
+        assert!(r.contest_choice.urls.is_empty());
+    }
+    let labels: HashSet<&str> = anonymized.results.iter().map(|r| r.contest_choice.text.as_str()).collect();
+    assert_eq!(anonymized.results.len(), labels.len());
+    assert!(labels.contains("Candidate A"));
+
+    // Pseudonyms can't be worked out from the choice IDs alone
+    let renamed: Vec<ContestChoice> = contest.choices().iter()
+        .map(|c| ContestChoice::new(c.id, &format!("{} Jr.", c.text)))
+        .collect();
+    let other_contest = ContestBuilder::new(2, &renamed)
+        .custom_id(contest.id())
+        .build();
+    let other = Tally::new(&other_contest)
+        .with_votes(gen_random_votes(200, &other_contest))
+        .result()
+        .anonymized();
+    let ids = |anonymized: &votes::AnonymizedResult| -> HashSet<i64> {
+        anonymized.results.iter().map(|r| r.contest_choice.id).collect()
+    };
+    assert!(ids(&anonymized).is_disjoint(&ids(&other)));
+
+    // Tied winners don't give away the order of the original IDs
+    let choices = vec![
+        ContestChoice::new(1, "Alice"),
+        ContestChoice::new(2, "Bob"),
+        ContestChoice::new(3, "Carol"),
+    ];
+    for contest_id in 1..=4 {
+        let contest = ContestBuilder::new(2, &choices)
+            .custom_id(contest_id)
+            .max_choices(1)
+            .build();
+        let votes: Vec<FlatVote> = [0, 1, 2, 0, 1]
+            .into_iter()
+            .map(|i| DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[i].clone())]).into())
+            .collect();
+        let anonymized = Tally::new(&contest).with_votes(votes).result().anonymized();
+        let winner_ids: Vec<i64> = anonymized.winners.iter().map(|w| w.id).collect();
+        let mut sorted = winner_ids.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, winner_ids);
+    }
+
+}
+
+
+#[test]
+fn winners_below_threshold() {
+
+    let choices = vec![
+        ContestChoice::new(1, "Alice"),
+        ContestChoice::new(2, "Bob"),
+        ContestChoice::new(3, "Carol"),
+    ];
+    let contest = ContestBuilder::new(2, &choices)
+        .max_choices(1)
+        .build();
+    let votes: Vec<FlatVote> = [(0, 6), (1, 3), (2, 1)].iter()
+        .flat_map(|(i, n)| (0..*n).map(move |_| *i))
+        .map(|i| DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[i].clone())]).into())
+        .collect();
+    let tally = Tally::new(&contest).with_votes(votes);
+    assert_eq!(0, tally.result().below_threshold_count);
+
+    // Bob ranks second with 3 votes out of 10
+    let result = tally.result_with_threshold(4);
+    assert_eq!(vec![choices[0].clone()], result.winners);
+    assert_eq!(1, result.below_threshold_count);
+    assert_eq!(0, result.choice_result_for(2).unwrap().winner_position);
+    assert_eq!(1, result.choice_result_for(1).unwrap().winner_position);
+    assert_eq!(tally.result(), tally.result_with_threshold(3));
+
+    assert_eq!(tally.result(), tally.result_with_pct_threshold(30.0));
+    let result = tally.result_with_pct_threshold(35.0);
+    assert_eq!(vec![choices[0].clone()], result.winners);
+    assert_eq!(1, result.below_threshold_count);
+
+    // The ranking and its margin don't depend on the threshold
+    let result = tally.result_with_threshold(7);
+    assert!(result.winners.is_empty());
+    assert_eq!(2, result.below_threshold_count);
+    assert_eq!(3, result.margin_of_victory);
+    assert_eq!(tally.result().margin_percentage, result.margin_percentage);
+    assert!(result.is_decisive(30.0));
+
+}
+
+
+#[test]
+fn margin_of_victory() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Mark Knopfler"),
+        ContestChoice::new(200, "Eric Clapton"),
+        ContestChoice::new(300, "Jimmy Page"),
+    ];
+    let contest = ContestBuilder::new(3, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+    let votes_for = |counts: &[usize]| -> Vec<FlatVote> {
+        counts.iter().enumerate()
+            .flat_map(|(i, n)| (0..*n).map(move |_| i))
+            .map(|i| DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[i].clone())]).into())
+            .collect()
+    };
+
+    // One vote ahead out of six
+    let result = Tally::new(&contest).with_votes(votes_for(&[3, 2, 1])).result();
+    assert_eq!(1, result.margin_of_victory);
+    assert!((result.margin_percentage - 100.0 / 6.0).abs() < 1e-9);
+
+    // No margin on a tie for first place
+    let result = Tally::new(&contest).with_votes(votes_for(&[3, 3, 2])).result();
+    assert_eq!(0, result.margin_of_victory);
+    assert_eq!(0.0, result.margin_percentage);
+
+}
+
+
+#[test]
+fn result_for_single_choice() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Mark Knopfler"),
+        ContestChoice::new(200, "Eric Clapton"),
+        ContestChoice::new(300, "Jimmy Page"),
+    ];
+    let contest = ContestBuilder::new(3, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+    let votes: Vec<FlatVote> = [0, 0, 0, 1, 1, 2].iter()
+        .map(|i| DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[*i].clone())]).into())
+        .collect();
+    let tally = Tally::new(&contest).with_votes(votes);
+
+    // Counted without ranking
+    let single_res = tally.result_for_choice(200)
+        .expect("Failed to count votes for a single choice");
+    assert_eq!(choices[1], single_res.contest_choice);
+    assert_eq!(2, single_res.total_count);
+    assert_eq!(0, single_res.winner_position);
+    assert!(tally.result_for_choice(999).is_none());
+
+}
+
+
+#[test]
+fn running_totals() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Mark Knopfler"),
+        ContestChoice::new(200, "Eric Clapton"),
+        ContestChoice::new(300, "Jimmy Page"),
+        ContestChoice::new(400, "Brian May"),
+    ];
+    let mut contest = ContestBuilder::new(3, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+    let votes: Vec<FlatVote> = [2, 0, 0, 1, 1, 2].iter()
+        .map(|i| DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[*i].clone())]).into())
+        .collect();
+    let tally = Tally::new(&contest).with_votes(votes.clone());
+
+    // Same totals as the full count, with ties ranked by ID
+    let result = tally.result();
+    assert_eq!(tally.counts_all(), tally.count());
+    assert_eq!(vec![(100, 2), (200, 2), (300, 2)], tally.count_sorted());
+    let ranked: Vec<(i64, u64)> = result.results.iter()
+        .filter(|r| r.total_count > 0)
+        .map(|r| (r.contest_choice.id, r.total_count))
+        .collect();
+    assert_eq!(ranked, tally.count_sorted());
+
+    // Disqualified choices keep their votes
+    contest.disqualify_choice(100);
+    let tally = Tally::new(&contest).with_votes(votes);
+    assert_eq!(vec![(100, 2), (200, 2), (300, 2)], tally.count_sorted());
+    assert_eq!(None, tally.result().choice_result_for(100));
+
+}
+
+
+#[test]
+fn count_votes_per_choice() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Mark Knopfler"),
+        ContestChoice::new(200, "Eric Clapton"),
+        ContestChoice::new(300, "Jimmy Page"),
+        ContestChoice::new(400, "Brian May"),
+    ];
+    let contest = ContestBuilder::new(3, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+    let mut votes: Vec<FlatVote> = [0, 0, 0, 1, 1, 2].iter()
+        .map(|i| DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[*i].clone())]).into())
+        .collect();
+    votes.push(FlatVote::blank(&contest));
+    let tally = Tally::new(&contest).with_votes(votes);
+
+    assert_eq!(3, tally.count_for(100));
+    assert_eq!(0, tally.count_for(400));
+    assert_eq!(0, tally.count_for(999));
+
+    // Only choices with votes are listed
+    let counts = tally.counts_all();
+    assert_eq!(3, counts.len());
+    assert_eq!(Some(&1), counts.get(&300));
+    assert_eq!(None, counts.get(&400));
+
+}
+
+
+#[test]
+fn look_up_results_by_choice_and_position() {
+
+    let choices = vec![
+        ContestChoice::new(100, "John Lennon"),
+        ContestChoice::new(200, "Paul McCartney"),
+        ContestChoice::new(300, "George Harrison"),
+        ContestChoice::new(400, "Ringo Starr"),
+    ];
+    let contest = ContestBuilder::new(3, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+    let votes: Vec<FlatVote> = [0, 0, 0, 1, 1, 1, 2, 2, 3].iter()
+        .map(|i| DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[*i].clone())]).into())
+        .collect();
+    let result = Tally::new(&contest).with_votes(votes).result();
+
+    // Tied winners are grouped together
+    let by_position = result.winners_by_position();
+    assert_eq!(2, by_position.len());
+    assert_eq!(vec![&choices[0], &choices[1]], by_position[&1]);
+    assert_eq!(vec![&choices[2]], by_position[&2]);
+
+    let lennon = result.choice_result_for(100)
+        .expect("Failed to find choice among contest results");
+    assert_eq!(3, lennon.total_count);
+    assert_eq!(1, result.choice_result_for(400).expect("Failed to find choice among contest results").total_count);
+    assert_eq!(None, result.choice_result_for(999));
+
+}
+
+
+#[test]
+fn clone_tally() {
+
+    let contest = gen_random_contest(3, gen_random_choices(10));
+    let mut tally = Tally::new(&contest)
+        .with_votes(gen_random_votes(20, &contest));
+
+    // Clones are counted the same and don't follow later changes
+    let original = tally.clone();
+    assert_eq!(tally, original);
+    assert_eq!(tally.result(), original.result());
+    tally.replace_votes(gen_random_votes(5, &contest));
+    assert_eq!(20, original.votes().len());
+    assert_eq!(5, tally.votes().len());
+
+}
+
+
+#[test]
+fn valid_and_invalid_votes() {
+
+    let choices = gen_random_choices(3);
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .build();
+    let vote = FlatVote::from(DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[0].clone())]));
+    let tally = Tally::new(&contest)
+        .with_votes(vec![FlatVote::blank(&contest), vote.clone(), FlatVote::blank(&contest), vote]);
+
+    // Votes are split as they are counted
+    let result = tally.result();
+    assert_eq!(result.total_invalid_votes as usize, tally.invalid_vote_count());
+    assert_eq!(result.total_valid_votes as usize, tally.valid_vote_count());
+    assert_eq!(2, tally.invalid_votes().count());
+    assert!(tally.invalid_votes().all(FlatVote::is_invalid));
+    assert_eq!(2, tally.valid_votes().count());
+    assert!(!tally.valid_votes().any(FlatVote::is_invalid));
+
+}
+
+
+#[test]
+fn count_and_check_contest_choices() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Jimi Hendrix"),
+        ContestChoice::new(200, "Stevie Ray Vaughan"),
+    ];
+    let valid = ContestBuilder::new(1, &choices)
+        .custom_id(1)
+        .build();
+    assert!(valid.is_valid());
+    assert_eq!(2, valid.num_choices());
+
+    // Deserialized contests skip the builder checks
+    let json = valid.to_json()
+        .expect("Failed to encode contest")
+        .replace("\"id\": 200", "\"id\": 100");
+    let invalid = Contest::from_json(&json)
+        .expect("Failed to decode contest");
+    assert_eq!(2, invalid.num_choices());
+    assert!(!invalid.is_valid());
+
+}
+
+
+#[test]
+fn contest_result_losers() {
+
+    let choices = vec![
+        ContestChoice::new(300, "Aretha Franklin"),
+        ContestChoice::new(100, "Otis Redding"),
+        ContestChoice::new(200, "James Brown"),
+    ];
+    let result_with_winners = |num_winners: i64| -> ContestResult {
+        let contest = ContestBuilder::new(num_winners, &choices)
+            .max_choices(1)
+            .min_choices(1)
+            .build();
+        let votes: Vec<FlatVote> = [1, 2, 2, 0, 0, 0].iter()
+            .map(|i| DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[*i].clone())]).into())
+            .collect();
+        Tally::new(&contest).with_votes(votes).result()
+    };
+
+    // Everyone but the winner, in order
+    let result = result_with_winners(1);
+    assert_eq!(vec![&choices[2], &choices[1]], result.losers());
+    assert_eq!(Some(&choices[2]), result.runner_up());
+
+    // Nobody loses when everyone wins
+    let result = result_with_winners(3);
+    assert!(result.losers().is_empty());
+    assert_eq!(None, result.runner_up());
+
+}
+
+
+#[test]
+fn contest_result_top_n() {
+
+    let choices = vec![
+        ContestChoice::new(300, "Aretha Franklin"),
+        ContestChoice::new(100, "Otis Redding"),
+        ContestChoice::new(200, "James Brown"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(1)
+        .min_choices(1)
+        .build();
+    let votes: Vec<FlatVote> = [1, 2, 2, 0, 0, 0].iter()
+        .map(|i| DecodedContestVote::new(&contest, vec![DecodedVoteChoice::new(choices[*i].clone())]).into())
+        .collect();
+    let result = Tally::new(&contest).with_votes(votes).result();
+
+    // Top results regardless of the single winner
+    let ids = |results: Vec<&votes::ContestChoiceResult>| -> Vec<i64> {
+        results.iter().map(|r| r.contest_choice.id).collect()
+    };
+    assert_eq!(vec![300, 200], ids(result.top_n(2)));
+    assert_eq!(vec![300, 200, 100], ids(result.top_n(5)));
+    assert!(result.top_n(0).is_empty());
+
+}
+
+
+#[test]
+fn exhausted_ballots_between_rounds() {
+
+    let choices = vec![
+        ContestChoice::new(1, "Alice"),
+        ContestChoice::new(2, "Bob"),
+        ContestChoice::new(3, "Carol"),
+        ContestChoice::new(4, "Dave"),
+    ];
+    let contest = ContestBuilder::new(1, &choices)
+        .max_choices(2)
+        .build();
+    let vote = |ids: &[usize]| -> FlatVote {
+        DecodedContestVote::new(&contest, ids.iter().map(|i| DecodedVoteChoice::new(choices[*i].clone())).collect()).into()
+    };
+    let mut votes = vec![];
+    votes.extend((0..5).map(|_| vote(&[0])));
+    votes.extend((0..4).map(|_| vote(&[1])));
+    votes.extend((0..3).map(|_| vote(&[2, 1])));
+    votes.extend((0..2).map(|_| vote(&[3])));
+    let tally = Tally::new(&contest).with_votes(votes);
+
+    // A single count has no exhausted ballots
+    let result = tally.result();
+    assert_eq!(0, result.exhausted_ballots);
+    assert_eq!(0.0, result.exhaustion_rate());
+
+    // Dave comes last, and his ballots have nothing left but are still valid
+    let (next, _) = tally.eliminate_last();
+    assert_eq!(14, next.votes().len());
+    assert_eq!(14, next.result().total_valid_votes);
+
+    // They are exhausted from the second round on
+    let result = tally.multi_round_result();
+    let exhausted: Vec<u64> = result.rounds.iter().map(|(r, _)| r.exhausted_ballots).collect();
+    assert_eq!(vec![0, 2, 2], exhausted);
+    assert_eq!(0.0, result.rounds[0].0.exhaustion_rate());
+    assert!((result.rounds[1].0.exhaustion_rate() - 2.0 / 14.0).abs() < 1e-9);
+
+}
+
+
+#[test]
+fn votes_for_choice_id() {
+
+    let choices = vec![
+        ContestChoice::new(100, "Alice"),
+        ContestChoice::new(200, "Bob"),
+        ContestChoice::new(300, "Carol"),
+    ];
+    let contest = ContestBuilder::new(2, &choices)
+        .max_choices(2)
+        .build();
+    let vote = |ids: &[usize]| -> FlatVote {
+        DecodedContestVote::new(&contest, ids.iter().map(|i| DecodedVoteChoice::new(choices[*i].clone())).collect()).into()
+    };
+    let tally = Tally::new(&contest)
+        .with_votes(vec![vote(&[0, 1]), vote(&[1]), vote(&[0]), vote(&[1, 2])]);
+
+    // Ballots behind each count
+    assert_eq!(3, tally.votes_for_choice_id(200).len());
+    assert!(tally.votes_for_choice_id(200).iter().all(|v| v.contains_choice_id(200)));
+    assert_eq!(vec![&tally.votes()[3]], tally.votes_for_choice_id(300));
+    assert!(tally.votes_for_choice_id(400).is_empty());
+
+}